@@ -0,0 +1,50 @@
+//! """Compares eager and lazy coalescing on a delete-heavy trace.
+//!
+//! Each round allocates `BLOCKS` adjacent 8-byte blocks, frees them all in address order and
+//! then makes one allocation spanning the whole arena, which in lazy mode pays for the
+//! deferred merging. The fastest of `ROUNDS` rounds is reported for each phase.
+//!
+//! Run with `cargo bench --bench coalesce_modes`.
+
+use memory_manager::{CoalesceMode, MemoryManager};
+use std::time::{Duration, Instant};
+
+const BLOCKS: usize = 6_000;
+const BLOCK_SIZE: usize = 8;
+const ROUNDS: usize = 20;
+
+/// """Times the delete burst and the following large insert for one coalescing mode.
+///
+/// Args:
+///     mode (CoalesceMode): The coalescing mode to measure.
+///
+/// Returns:
+///     (Duration, Duration): The fastest delete burst and the fastest large insert.
+fn measure(mode: CoalesceMode) -> (Duration, Duration) {
+    let mut best = (Duration::MAX, Duration::MAX);
+    for _ in 0..ROUNDS {
+        let mut mm = MemoryManager::builder().capacity(BLOCKS * BLOCK_SIZE).coalesce_mode(mode).build();
+        let ids: Vec<usize> = (0..BLOCKS).map(|_| mm.insert(BLOCK_SIZE, b"").unwrap()).collect();
+
+        let started = Instant::now();
+        for id in ids {
+            mm.delete(id).unwrap();
+        }
+        let deletes = started.elapsed();
+
+        let started = Instant::now();
+        mm.insert(BLOCKS * BLOCK_SIZE, b"").unwrap();
+        let insert = started.elapsed();
+
+        best = (best.0.min(deletes), best.1.min(insert));
+    }
+    best
+}
+
+fn main() {
+    println!("{} adjacent {}-byte frees, then one {}-byte insert (best of {} rounds)", BLOCKS, BLOCK_SIZE, BLOCKS * BLOCK_SIZE, ROUNDS);
+    for mode in [CoalesceMode::Eager, CoalesceMode::Lazy] {
+        let (deletes, insert) = measure(mode);
+        println!("{:?}: deletes {:?}, insert {:?}, total {:?}", mode, deletes, insert, deletes + insert);
+    }
+}
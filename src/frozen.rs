@@ -0,0 +1,81 @@
+use crate::{MemoryManager, MemoryStats, Region};
+use std::io::{self, Write};
+
+/// """A read-only view of a MemoryManager whose allocations are final.
+///
+/// Created by `MemoryManager::freeze`, which consumes the manager, so no insert, delete or
+/// update can happen afterwards. Only read operations are exposed.
+///
+/// Attributes:
+///     inner (MemoryManager): The frozen memory manager.
+pub struct FrozenManager {
+    inner: MemoryManager,
+}
+
+impl FrozenManager {
+    /// """Wraps a memory manager so that it can no longer be modified.
+    ///
+    /// Args:
+    ///     manager (MemoryManager): The memory manager to freeze.
+    ///
+    /// Returns:
+    ///     FrozenManager: The read-only view.
+    /// """
+    pub(crate) fn new(manager: MemoryManager) -> Self {
+        Self { inner: manager }
+    }
+
+    /// """Finds the data associated with an allocated block by its unique ID.
+    ///
+    /// Args:
+    ///     id (usize): The unique allocation ID to look up.
+    ///
+    /// Returns:
+    ///     Option<&[u8]>: A slice of the data stored in the allocated block if found, or None otherwise.
+    /// """
+    pub fn find(&self, id: usize) -> Option<&[u8]> {
+        self.inner.find(id)
+    }
+
+    /// """Finds the starting address and data of an allocated block by its unique ID.
+    ///
+    /// Args:
+    ///     id (usize): The unique allocation ID to look up.
+    ///
+    /// Returns:
+    ///     Option<(usize, &[u8])>: The block's starting address and data if found, or None otherwise.
+    /// """
+    pub fn find_with_addr(&self, id: usize) -> Option<(usize, &[u8])> {
+        self.inner.find_with_addr(id)
+    }
+
+    /// """Summarizes memory usage at the time the manager was frozen.
+    ///
+    /// Returns:
+    ///     MemoryStats: A snapshot of the manager's statistics.
+    /// """
+    pub fn stats(&self) -> MemoryStats {
+        self.inner.stats()
+    }
+
+    /// """Dumps the frozen memory layout, listing free and allocated blocks.
+    ///
+    /// Args:
+    ///     out (&mut dyn Write): The sink the dump is written to.
+    ///
+    /// Returns:
+    ///     io::Result<()>: Ok(()) on success, or the error returned by the sink.
+    /// """
+    pub fn dump_to(&self, out: &mut dyn Write) -> io::Result<()> {
+        self.inner.dump_to(out)
+    }
+
+    /// """Lists every free and allocated region in ascending address order.
+    ///
+    /// Returns:
+    ///     Vec<Region>: The regions sorted by starting address.
+    /// """
+    pub fn regions(&self) -> Vec<Region> {
+        self.inner.regions()
+    }
+}
@@ -1,11 +1,235 @@
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt;
+use std::fs;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::io::{self, Write};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
+pub mod frozen;
 pub mod proc;
+pub mod sync;
 
-/// """Total size of the managed memory.
+use frozen::FrozenManager;
+
+/// """Default total size of the managed memory.
 ///
-/// This constant defines the overall number of bytes available.
+/// This constant defines the number of bytes available unless another capacity is configured.
 const MEMORY_SIZE: usize = 65535; // Total memory size
+const MAX_GROWN_CAPACITY: usize = 1 << 32; // Largest memory a growable manager may grow to
+
+/// """Errors that can be returned by fallible MemoryManager operations.
+///
+/// Variants:
+///     OutOfMemory: No free block is large enough to satisfy the requested size.
+///     UnknownId: The ID does not refer to a live allocation.
+///     DataTooLarge: The data is longer than the block it is written to.
+///     LiveAllocations: The operation requires that no allocations exist, but some do.
+///     AllocationTooLarge: The requested size exceeds the configured maximum single-allocation size.
+///     Protected: The allocation is write-protected and cannot be modified.
+///     InvalidAlignment: The requested alignment is not a power of two.
+///     RegionNotFree: Some of the requested address range is allocated or outside memory.
+///     SizeMismatch: The data length is not exactly the size of the block it replaces.
+///     UntrackedMemory: A replacement free list leaves some unallocated memory out.
+///     StaleHandle: The handle's ID was freed or has since been reissued to another allocation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AllocError {
+    OutOfMemory { requested: usize },
+    UnknownId(usize),
+    DataTooLarge { len: usize, size: usize },
+    LiveAllocations(usize),
+    AllocationTooLarge { requested: usize, max: usize },
+    Protected(usize),
+    InvalidAlignment(usize),
+    RegionNotFree { start: usize, size: usize },
+    SizeMismatch { len: usize, size: usize },
+    UntrackedMemory { start: usize, size: usize },
+    StaleHandle { id: usize, generation: u32 },
+}
+
+impl fmt::Display for AllocError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AllocError::OutOfMemory { requested } => {
+                write!(f, "out of memory: no free block fits {} bytes", requested)
+            }
+            AllocError::UnknownId(id) => write!(f, "ID {} not found", id),
+            AllocError::DataTooLarge { len, size } => {
+                write!(f, "new data ({} bytes) exceeds allocated block size ({} bytes)", len, size)
+            }
+            AllocError::LiveAllocations(count) => write!(f, "{} allocations are still live", count),
+            AllocError::AllocationTooLarge { requested, max } => {
+                write!(f, "allocation of {} bytes exceeds the maximum of {} bytes", requested, max)
+            }
+            AllocError::Protected(id) => write!(f, "ID {} is write-protected", id),
+            AllocError::InvalidAlignment(align) => write!(f, "alignment {} is not a power of two", align),
+            AllocError::RegionNotFree { start, size } => {
+                write!(f, "the {} bytes starting at address {} are not free", size, start)
+            }
+            AllocError::SizeMismatch { len, size } => {
+                write!(f, "new data ({} bytes) does not match allocated block size ({} bytes)", len, size)
+            }
+            AllocError::UntrackedMemory { start, size } => {
+                write!(f, "the {} free bytes starting at address {} would not be tracked", size, start)
+            }
+            AllocError::StaleHandle { id, generation } => {
+                write!(f, "handle for ID {} generation {} is stale", id, generation)
+            }
+        }
+    }
+}
+
+impl std::error::Error for AllocError {}
+
+/// """The number base used when printing memory addresses.
+///
+/// Variants:
+///     Hex: Zero-padded hexadecimal with a `0x` prefix, e.g. `0x000a`.
+///     Dec: Plain decimal, e.g. `10`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AddressRadix {
+    #[default]
+    Hex,
+    Dec,
+}
+
+/// """How allocation and free events are written by command processing.
+///
+/// Variants:
+///     Human: Readable sentences, e.g. `Allocated ID: 3`.
+///     Csv: `op,id,start,size,timestamp` rows for spreadsheet ingestion, where `op` is
+///         `insert` or `delete`, `start` is decimal and `timestamp` is the operation count.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LogFormat {
+    #[default]
+    Human,
+    Csv,
+}
+
+/// """What command processing does when DELETE names an ID that is not allocated.
+///
+/// Variants:
+///     Warn: Report the error and continue with the next command.
+///     Fail: Stop processing with an error naming the failing line, as for a double free.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum UnknownPolicy {
+    #[default]
+    Warn,
+    Fail,
+}
+
+/// """The policy used by `insert` to choose a free block.
+///
+/// Variants:
+///     BestFit: Use the smallest free block that fits the request.
+///     FirstFit: Use the lowest-addressed free block that fits the request.
+///     Lifo: Reuse the most recently freed block that fits, falling back to best-fit. This
+///         trades fit quality for temporal locality.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AllocStrategy {
+    #[default]
+    BestFit,
+    FirstFit,
+    Lifo,
+}
+
+/// """When freed blocks are merged with adjacent free space.
+///
+/// Variants:
+///     Eager: Every free immediately merges the region with its free neighbours. Each free
+///         pays for a neighbour lookup, but the free list always holds maximal regions.
+///     Lazy: Frees only add the region to the free list. Merging is deferred to an explicit
+///         `coalesce()` or to the next allocation that would otherwise fail, which moves merge
+///         work out of bursts of deletes at the cost of a more fragmented free list in between.
+///
+/// `benches/coalesce_modes.rs` frees 6,000 adjacent 8-byte blocks and then makes one
+/// arena-sized insert. On one development machine the delete burst took about 0.6 ms in
+/// both modes, since the neighbour lookup is a small part of each free, and the insert took
+/// about 1 µs in eager mode against 45 µs in lazy mode, which pays for the deferred coalesce
+/// pass there. Lazy mode changes when merging happens more than what it costs in total.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CoalesceMode {
+    #[default]
+    Eager,
+    Lazy,
+}
+
+/// """A free block offered to a custom selector as a candidate for an allocation.
+///
+/// Attributes:
+///     start (usize): The starting index of the free block.
+///     size (usize): The size of the free block in bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CandidateBlock {
+    pub start: usize,
+    pub size: usize,
+}
+
+/// """A custom free-block selection policy.
+///
+/// The selector receives every free block large enough for the request, in ascending address
+/// order, and returns the index of the one to use, or None to fail the allocation. It must be
+/// `Send` so a manager with a selector can still be shared through `SyncMemoryManager`.
+pub type Selector = Box<dyn Fn(&[CandidateBlock]) -> Option<usize> + Send>;
+
+/// """An allocation ID paired with the generation it was issued in.
+///
+/// IDs can be issued again after `reset_ids`, `reset` or `renumber`, and each reissue bumps the
+/// ID's generation, so a handle kept past its allocation's lifetime is detected as stale
+/// instead of silently addressing the newer allocation.
+pub type Handle = (usize, u32);
+
+/// """Describes what occupies a region of memory.
+///
+/// Variants:
+///     Free: The region is free and available for allocation.
+///     Allocated: The region holds the allocation with the given ID.
+///     Reserved: The region was withdrawn by `reserve_reserved` and is never allocated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RegionKind {
+    Free,
+    Allocated { id: usize },
+    Reserved,
+}
+
+/// """A contiguous region of memory in the address-ordered layout.
+///
+/// Attributes:
+///     start (usize): The starting index of the region.
+///     size (usize): The size of the region in bytes.
+///     kind (RegionKind): Whether the region is free, allocated or reserved.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Region {
+    pub start: usize,
+    pub size: usize,
+    pub kind: RegionKind,
+}
+
+/// """A record of every allocation's content at one point in time, taken by `checkpoint`.
+///
+/// Only a hash of each allocation's bytes is kept, so a checkpoint stays small however
+/// large the allocations are.
+///
+/// Attributes:
+///     hashes (BTreeMap<usize, u64>): Maps each allocation ID to a hash of its contents.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Checkpoint {
+    hashes: BTreeMap<usize, u64>,
+}
+
+/// """How one allocation differs from a checkpoint.
+///
+/// Variants:
+///     Added: The ID was allocated after the checkpoint.
+///     Removed: The ID was freed after the checkpoint.
+///     Modified: The ID still exists but its contents changed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Change {
+    Added(usize),
+    Removed(usize),
+    Modified(usize),
+}
 
 /// """Represents a block of memory managed by the MemoryManager.
 ///
@@ -14,13 +238,175 @@ const MEMORY_SIZE: usize = 65535; // Total memory size
 ///     size (usize): The size of the memory block in bytes.
 ///     allocated (bool): Flag indicating if the block is currently allocated.
 ///     id (Option<usize>): The unique identifier for the allocated block, if any.
-#[derive(Debug)]
+///     created (usize): The allocation tick at which the block was allocated (0 for free blocks).
+///     protected (bool): Whether writes to the allocated block are rejected.
+///     tag (u16): A caller-defined category for the allocated block (0 unless set by `insert_tagged`).
+///     generation (u32): How many times the block's ID had been issued before it (0 for free blocks).
+#[derive(Debug, Clone)]
 #[allow(dead_code)]
 struct MemoryBlock {
     start: usize,
     size: usize,
     allocated: bool,
     id: Option<usize>,
+    created: usize,
+    protected: bool,
+    tag: u16,
+    generation: u32,
+}
+
+/// """A point-in-time summary of how memory is being used.
+///
+/// Attributes:
+///     total_bytes (usize): The total size of the managed memory.
+///     used_bytes (usize): The number of bytes held by allocated blocks.
+///     free_bytes (usize): The number of bytes held by free blocks.
+///     allocation_count (usize): The number of live allocations.
+///     free_block_count (usize): The number of blocks in the free list.
+///     largest_gap (usize): The size of the largest contiguous free region.
+///     reclaimable_by_compaction (usize): Free bytes outside the largest gap, i.e. the space
+///         a single large allocation can only use after compaction.
+///     largest_gap_after_compaction (usize): The size of the largest contiguous free region `compact`
+///         would leave; less than `free_bytes` only when reserved ranges split the free space.
+///     operation_count (usize): The number of commands processed since creation or the last reset.
+///     operations_remaining (Option<usize>): How many more commands may be processed before the
+///         `max_total_operations` limit stops processing, or None if there is no limit.
+///     bytes_allocated_total (usize): The cumulative size of every allocation made since creation or
+///         the last reset; unlike `used_bytes` it never decreases when blocks are freed.
+///     blocks_scanned (usize): The total number of free blocks examined while choosing blocks for
+///         inserts since creation or the last reset.
+///     longest_scan (usize): The most free blocks examined by a single insert.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MemoryStats {
+    pub total_bytes: usize,
+    pub used_bytes: usize,
+    pub free_bytes: usize,
+    pub allocation_count: usize,
+    pub free_block_count: usize,
+    pub largest_gap: usize,
+    pub reclaimable_by_compaction: usize,
+    pub largest_gap_after_compaction: usize,
+    pub operation_count: usize,
+    pub operations_remaining: Option<usize>,
+    pub bytes_allocated_total: usize,
+    pub blocks_scanned: usize,
+    pub longest_scan: usize,
+}
+
+impl MemoryStats {
+    /// """Computes the share of free bytes that lie outside the largest contiguous free region.
+    ///
+    /// Returns:
+    ///     f64: The fragmentation ratio between 0.0 and 1.0, or 0.0 when there is no free space.
+    /// """
+    pub fn fragmentation(&self) -> f64 {
+        if self.free_bytes == 0 {
+            return 0.0;
+        }
+        self.reclaimable_by_compaction as f64 / self.free_bytes as f64
+    }
+
+    /// """Computes the fragmentation ratio `compact` would leave.
+    ///
+    /// Like `fragmentation`, this is 0.0 when there is no free space, so the two can be
+    /// compared directly.
+    ///
+    /// Returns:
+    ///     f64: The share of free bytes outside the largest region left by compaction.
+    /// """
+    pub fn fragmentation_floor(&self) -> f64 {
+        if self.free_bytes == 0 {
+            return 0.0;
+        }
+        (self.free_bytes - self.largest_gap_after_compaction) as f64 / self.free_bytes as f64
+    }
+
+    /// """Computes the mean size of the live allocations.
+    ///
+    /// Returns:
+    ///     f64: `used_bytes / allocation_count`, or 0.0 when nothing is allocated.
+    /// """
+    pub fn average_allocation_size(&self) -> f64 {
+        if self.allocation_count == 0 {
+            return 0.0;
+        }
+        self.used_bytes as f64 / self.allocation_count as f64
+    }
+}
+
+impl fmt::Display for MemoryStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Memory Stats:")?;
+        writeln!(f, "Total: {} bytes", self.total_bytes)?;
+        writeln!(f, "Used: {} bytes in {} allocations", self.used_bytes, self.allocation_count)?;
+        writeln!(f, "Average allocation: {:.2} bytes", self.average_allocation_size())?;
+        writeln!(f, "Free: {} bytes in {} blocks", self.free_bytes, self.free_block_count)?;
+        writeln!(f, "Largest gap: {} bytes", self.largest_gap)?;
+        writeln!(f, "Largest gap after compaction: {} bytes", self.largest_gap_after_compaction)?;
+        writeln!(f, "Reclaimable by compaction: {} bytes", self.reclaimable_by_compaction)?;
+        writeln!(f, "Fragmentation: {:.2} (floor {:.2} after compaction)", self.fragmentation(), self.fragmentation_floor())?;
+        writeln!(f, "Operations: {}", self.operation_count)?;
+        match self.operations_remaining {
+            Some(remaining) => writeln!(f, "Operations remaining: {}", remaining)?,
+            None => writeln!(f, "Operations remaining: unlimited")?,
+        }
+        writeln!(f, "Total allocated: {} bytes", self.bytes_allocated_total)?;
+        write!(f, "Free-list scan: {} blocks examined, longest {}", self.blocks_scanned, self.longest_scan)
+    }
+}
+
+/// """A snapshot of how a MemoryManager is configured.
+///
+/// Attributes:
+///     capacity (usize): The total size of the managed memory.
+///     fill_pattern (u8): The byte used to initialize memory and to wipe freed regions.
+///     zero_on_free (bool): Whether freed regions are overwritten with `fill_pattern`.
+///     strategy (AllocStrategy): The policy `insert` uses to choose a free block.
+///     custom_selector (bool): Whether a custom selector overrides `strategy`.
+///     coalesce_mode (CoalesceMode): When freed blocks are merged with free neighbours.
+///     max_alloc_size (Option<usize>): The largest size a single allocation may request, if limited.
+///     auto_compact_every (Option<usize>): The command interval for automatic compaction, if set.
+///     auto_compact_threshold (Option<f64>): The fragmentation that triggers automatic compaction, if set.
+///     max_total_operations (Option<usize>): The number of commands after which processing stops, if limited.
+///     strict (bool): Whether a failed ASSERT command stops command processing.
+///     unknown_policy (UnknownPolicy): Whether deleting an unknown ID stops command processing.
+///     radix (AddressRadix): The number base used when printing addresses.
+///     log_format (LogFormat): How allocation and free events are written by command processing.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ManagerConfig {
+    pub capacity: usize,
+    pub fill_pattern: u8,
+    pub zero_on_free: bool,
+    pub strategy: AllocStrategy,
+    pub custom_selector: bool,
+    pub coalesce_mode: CoalesceMode,
+    pub max_alloc_size: Option<usize>,
+    pub auto_compact_every: Option<usize>,
+    pub auto_compact_threshold: Option<f64>,
+    pub max_total_operations: Option<usize>,
+    pub strict: bool,
+    pub unknown_policy: UnknownPolicy,
+    pub radix: AddressRadix,
+    pub log_format: LogFormat,
+}
+
+impl fmt::Display for ManagerConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Manager Config:")?;
+        writeln!(f, "Capacity: {} bytes", self.capacity)?;
+        writeln!(f, "Fill pattern: {:#04x}", self.fill_pattern)?;
+        writeln!(f, "Zero on free: {}", self.zero_on_free)?;
+        writeln!(f, "Strategy: {:?}{}", self.strategy, if self.custom_selector { " (overridden by selector)" } else { "" })?;
+        writeln!(f, "Coalesce mode: {:?}", self.coalesce_mode)?;
+        writeln!(f, "Max allocation size: {:?}", self.max_alloc_size)?;
+        writeln!(f, "Auto-compact every: {:?}", self.auto_compact_every)?;
+        writeln!(f, "Auto-compact threshold: {:?}", self.auto_compact_threshold)?;
+        writeln!(f, "Max total operations: {:?}", self.max_total_operations)?;
+        writeln!(f, "Strict: {}", self.strict)?;
+        writeln!(f, "Unknown ID policy: {:?}", self.unknown_policy)?;
+        writeln!(f, "Radix: {:?}", self.radix)?;
+        write!(f, "Log format: {:?}", self.log_format)
+    }
 }
 
 /// """Manages dynamic memory allocation using a best-fit strategy.
@@ -30,233 +416,3397 @@ struct MemoryBlock {
 /// reading, updating, and dumping the current memory state.
 ///
 /// Attributes:
-///     memory ([u8; MEMORY_SIZE]): The underlying memory array.
-///     free_blocks (BTreeMap<usize, Vec<MemoryBlock>>): Maps block sizes to lists of free memory blocks.
+///     memory (Vec<u8>): The underlying memory buffer; its length is the manager's capacity.
+///     free_blocks (BTreeMap<usize, Vec<MemoryBlock>>): Maps block sizes to lists of free memory blocks,
+///         each list kept sorted by starting address.
 ///     allocated_blocks (BTreeMap<usize, MemoryBlock>): Maps unique allocation IDs to their corresponding allocated blocks.
 ///     next_id (usize): Next unique identifier for allocation.
+///     clock (usize): Allocation tick stamped on each new block; unlike IDs it is never restarted by `reset_ids`.
+///     by_address (BTreeSet<(usize, usize)>): The (start, ID) of every allocation, for address-ordered lookups.
+///     generations (BTreeMap<usize, u32>): The latest generation of every ID ever issued; kept across resets
+///         so that handles from before a reset are still recognised as stale.
+///     operations (usize): Number of commands processed since creation or the last reset.
+///     bytes_allocated_total (usize): Cumulative size of all allocations since creation or the last reset.
+///     blocks_scanned (usize): Free blocks examined by inserts since creation or the last reset.
+///     longest_scan (usize): The most free blocks examined by a single insert.
+///     strict (bool): Whether a failed ASSERT command stops command processing with an error.
+///     unknown_policy (UnknownPolicy): Whether deleting an unknown ID stops command processing.
+///     radix (AddressRadix): The number base used when printing addresses.
+///     log_format (LogFormat): How allocation and free events are written by command processing.
+///     fill_pattern (u8): The byte used to initialize memory and to wipe freed regions.
+///     zero_on_free (bool): Whether freed regions are overwritten with `fill_pattern`.
+///     strategy (AllocStrategy): The policy `insert` uses to choose a free block.
+///     coalesce_mode (CoalesceMode): Whether frees merge with adjacent free space immediately or lazily.
+///     selector (Option<Arc<Mutex<Selector>>>): A custom free-block selection policy that overrides `strategy`
+///         when set, shared so that cloning a manager shares its selector.
+///     freed_stack (Vec<(usize, usize)>): The (start, size) of freed blocks, most recent last, used by the `Lifo` strategy.
+///     reserved (Vec<(usize, usize)>): The (start, size) of ranges withdrawn by `reserve_reserved`, in address order.
+///     max_alloc_size (Option<usize>): The largest size a single allocation may request, if limited.
+///     auto_compact_every (Option<usize>): Command processing compacts after every this many commands, if set.
+///     auto_compact_threshold (Option<f64>): Command processing compacts once fragmentation reaches this ratio, if set.
+///     max_total_operations (Option<usize>): Command processing stops with an error once this many commands have run, if set.
+///     growable (bool): Whether a failed insert grows memory instead of failing (used for capacity planning).
+///     forced_failures (usize): Number of upcoming inserts forced to fail (`test-hooks` feature only).
+#[derive(Clone)]
 pub struct MemoryManager {
-    memory: [u8; MEMORY_SIZE],
+    memory: Vec<u8>,
     free_blocks: BTreeMap<usize, Vec<MemoryBlock>>, // Map from block size to free blocks
     allocated_blocks: BTreeMap<usize, MemoryBlock>,   // Map from ID to allocated block
+    by_address: BTreeSet<(usize, usize)>,             // Allocations ordered by start address
     next_id: usize,                                   // Unique ID for allocations
+    clock: usize,                                     // Tick stamped on each new allocation
+    generations: BTreeMap<usize, u32>,                // Latest generation of each issued ID
+    operations: usize,                                // Number of commands processed
+    bytes_allocated_total: usize,                     // Cumulative bytes ever allocated
+    blocks_scanned: usize,                            // Free blocks examined by inserts
+    longest_scan: usize,                              // Longest single insert scan
+    strict: bool,                                     // Stop processing on failed assertions
+    unknown_policy: UnknownPolicy,                    // Stop processing on unknown DELETEs
+    radix: AddressRadix,                              // Base used when printing addresses
+    log_format: LogFormat,                            // Human or CSV event output
+    fill_pattern: u8,                                 // Byte used to initialize and wipe memory
+    zero_on_free: bool,                               // Wipe regions when they are freed
+    strategy: AllocStrategy,                          // Policy for choosing a free block
+    coalesce_mode: CoalesceMode,                      // When freed blocks are merged
+    selector: Option<Arc<Mutex<Selector>>>,           // Custom selection policy, if any
+    freed_stack: Vec<(usize, usize)>,                 // Recently freed blocks for Lifo
+    reserved: Vec<(usize, usize)>,                    // Ranges never handed out
+    max_alloc_size: Option<usize>,                    // Largest allowed single allocation
+    auto_compact_every: Option<usize>,                // Compaction interval in commands
+    auto_compact_threshold: Option<f64>,              // Fragmentation that triggers compaction
+    max_total_operations: Option<usize>,              // Commands allowed before processing stops
+    growable: bool,                                   // Grow memory when an insert does not fit
+    #[cfg(feature = "test-hooks")]
+    forced_failures: usize,                           // Remaining inserts forced to fail
 }
 
-impl MemoryManager {
-    /// """Creates a new MemoryManager instance with the entire memory available as a single free block.
+/// """Configures and creates a MemoryManager.
+///
+/// Attributes:
+///     capacity (usize): The total size of the managed memory. Defaults to 65535 bytes.
+///     fill_pattern (u8): The byte used to initialize memory and to wipe freed regions. Defaults to 0x00.
+///     zero_on_free (bool): Whether freed regions are overwritten with `fill_pattern`. Defaults to false.
+///     strategy (AllocStrategy): The policy `insert` uses to choose a free block. Defaults to best-fit.
+///     coalesce_mode (CoalesceMode): When freed blocks are merged with free neighbours. Defaults to eager.
+///     max_alloc_size (Option<usize>): The largest size a single allocation may request. Defaults to no limit.
+///     auto_compact_every (Option<usize>): Compact after every this many processed commands. Defaults to never.
+///     auto_compact_threshold (Option<f64>): Compact after a command leaves fragmentation at or above
+///         this ratio. Defaults to never.
+///     max_total_operations (Option<usize>): Stop command processing with an error after this many
+///         commands. Defaults to no limit.
+#[derive(Debug, Clone)]
+pub struct MemoryManagerBuilder {
+    capacity: usize,
+    fill_pattern: u8,
+    zero_on_free: bool,
+    strategy: AllocStrategy,
+    coalesce_mode: CoalesceMode,
+    max_alloc_size: Option<usize>,
+    auto_compact_every: Option<usize>,
+    auto_compact_threshold: Option<f64>,
+    max_total_operations: Option<usize>,
+}
+
+impl Default for MemoryManagerBuilder {
+    fn default() -> Self {
+        Self {
+            capacity: MEMORY_SIZE,
+            fill_pattern: 0,
+            zero_on_free: false,
+            strategy: AllocStrategy::BestFit,
+            coalesce_mode: CoalesceMode::Eager,
+            max_alloc_size: None,
+            auto_compact_every: None,
+            auto_compact_threshold: None,
+            max_total_operations: None,
+        }
+    }
+}
+
+impl MemoryManagerBuilder {
+    /// """Sets the total size of the managed memory.
+    ///
+    /// Args:
+    ///     capacity (usize): The capacity in bytes.
     ///
     /// Returns:
-    ///     MemoryManager: A new instance with initialized memory and free block tracking.
+    ///     MemoryManagerBuilder: The updated builder.
     /// """
-    pub fn new() -> Self {
-        let mut free_map = BTreeMap::new();
-        free_map.insert(
-            MEMORY_SIZE,
-            vec![MemoryBlock {
-                start: 0,
-                size: MEMORY_SIZE,
-                allocated: false,
-                id: None,
-            }],
-        );
+    pub fn capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity;
+        self
+    }
 
-        Self {
-            memory: [0; MEMORY_SIZE],
-            free_blocks: free_map,
-            allocated_blocks: BTreeMap::new(),
-            next_id: 0,
-        }
+    /// """Sets the byte used to initialize memory and to wipe freed regions.
+    ///
+    /// A recognizable pattern such as 0xAA makes reads of uninitialized memory stand out.
+    ///
+    /// Args:
+    ///     pattern (u8): The fill byte.
+    ///
+    /// Returns:
+    ///     MemoryManagerBuilder: The updated builder.
+    /// """
+    pub fn fill_pattern(mut self, pattern: u8) -> Self {
+        self.fill_pattern = pattern;
+        self
     }
 
-    /// """Inserts data into memory using a best-fit allocation strategy.
+    /// """Sets whether freed regions are overwritten with the fill pattern.
     ///
-    /// This method searches for the smallest free memory block that can accommodate the requested size.
-    /// If a suitable block is found, it allocates the block, writes the data into memory,
-    /// and adjusts free block tracking accordingly.
+    /// Args:
+    ///     enabled (bool): True to wipe regions as they are freed.
+    ///
+    /// Returns:
+    ///     MemoryManagerBuilder: The updated builder.
+    /// """
+    pub fn zero_on_free(mut self, enabled: bool) -> Self {
+        self.zero_on_free = enabled;
+        self
+    }
+
+    /// """Sets the policy `insert` uses to choose a free block.
     ///
     /// Args:
-    ///     size (usize): The number of bytes to allocate.
-    ///     data (&[u8]): A byte slice containing the data to be stored.
+    ///     strategy (AllocStrategy): The allocation strategy.
     ///
     /// Returns:
-    ///     Option<usize>: A unique allocation ID if the allocation is successful, or None if insufficient space is available.
+    ///     MemoryManagerBuilder: The updated builder.
     /// """
-    pub fn insert(&mut self, size: usize, data: &[u8]) -> Option<usize> {
-        // Find the smallest free block (using BTreeMap range) that fits the requested size.
-        let mut chosen_key = None;
-        let mut chosen_index = None;
+    pub fn strategy(mut self, strategy: AllocStrategy) -> Self {
+        self.strategy = strategy;
+        self
+    }
 
-        // Iterate over free block sizes starting from `size`
-        for (&free_size, blocks) in self.free_blocks.range_mut(size..) {
-            if let Some((index, block)) = blocks.iter().enumerate().find(|(_, block)| block.size >= size) {
-                chosen_key = Some(free_size);
-                chosen_index = Some(index);
-                break;
-            }
-        }
+    /// """Sets when freed blocks are merged with adjacent free space.
+    ///
+    /// Args:
+    ///     mode (CoalesceMode): The coalescing mode.
+    ///
+    /// Returns:
+    ///     MemoryManagerBuilder: The updated builder.
+    /// """
+    pub fn coalesce_mode(mut self, mode: CoalesceMode) -> Self {
+        self.coalesce_mode = mode;
+        self
+    }
 
-        if let (Some(key), Some(index)) = (chosen_key, chosen_index) {
-            // Remove the chosen block from free_blocks.
-            let block = {
-                let blocks = self.free_blocks.get_mut(&key).unwrap();
-                blocks.remove(index)
-            };
-            // Clean up the entry if no more blocks exist for that key.
-            if let Some(blocks) = self.free_blocks.get(&key) {
-                if blocks.is_empty() {
-                    self.free_blocks.remove(&key);
-                }
-            }
+    /// """Sets the largest size a single allocation may request.
+    ///
+    /// Larger requests fail with `AllocationTooLarge` even when enough free space exists,
+    /// so a single runaway request cannot exhaust the arena.
+    ///
+    /// Args:
+    ///     max (usize): The maximum allocation size in bytes.
+    ///
+    /// Returns:
+    ///     MemoryManagerBuilder: The updated builder.
+    /// """
+    pub fn max_alloc_size(mut self, max: usize) -> Self {
+        self.max_alloc_size = Some(max);
+        self
+    }
 
-            // Allocate and write data into memory.
-            let new_id = self.next_id;
-            self.next_id += 1;
+    /// """Makes command processing compact memory after every `commands` commands.
+    ///
+    /// Args:
+    ///     commands (usize): The interval in processed commands; 0 disables it.
+    ///
+    /// Returns:
+    ///     MemoryManagerBuilder: The updated builder.
+    /// """
+    pub fn auto_compact_every(mut self, commands: usize) -> Self {
+        self.auto_compact_every = (commands > 0).then_some(commands);
+        self
+    }
+
+    /// """Makes command processing compact memory whenever fragmentation reaches a threshold.
+    ///
+    /// Args:
+    ///     threshold (f64): The `fragmentation()` ratio that triggers compaction after a command.
+    ///
+    /// Returns:
+    ///     MemoryManagerBuilder: The updated builder.
+    /// """
+    pub fn auto_compact_threshold(mut self, threshold: f64) -> Self {
+        self.auto_compact_threshold = Some(threshold);
+        self
+    }
 
-            // Ensure we copy only up to 'size' bytes.
-            self.memory[block.start..block.start + size]
-                .copy_from_slice(&data[..size]);
+    /// """Limits how many commands command processing may run before stopping with an error.
+    ///
+    /// This guards against a runaway or endlessly generated trace consuming unbounded time.
+    /// The count is the manager's operation count, so it restarts on `reset`.
+    ///
+    /// Args:
+    ///     limit (usize): The maximum number of commands to process.
+    ///
+    /// Returns:
+    ///     MemoryManagerBuilder: The updated builder.
+    /// """
+    pub fn max_total_operations(mut self, limit: usize) -> Self {
+        self.max_total_operations = Some(limit);
+        self
+    }
 
-            // Store the allocated block.
-            let allocated_block = MemoryBlock {
-                start: block.start,
-                size,
-                allocated: true,
-                id: Some(new_id),
-            };
-            self.allocated_blocks.insert(new_id, allocated_block);
+    /// """Creates a MemoryManager with the configured settings.
+    ///
+    /// Returns:
+    ///     MemoryManager: A new instance with the entire memory available as a single free block.
+    /// """
+    pub fn build(self) -> MemoryManager {
+        let mut manager = MemoryManager::with_capacity(self.capacity);
+        manager.fill_pattern = self.fill_pattern;
+        manager.zero_on_free = self.zero_on_free;
+        manager.strategy = self.strategy;
+        manager.coalesce_mode = self.coalesce_mode;
+        manager.max_alloc_size = self.max_alloc_size;
+        manager.auto_compact_every = self.auto_compact_every;
+        manager.auto_compact_threshold = self.auto_compact_threshold;
+        manager.max_total_operations = self.max_total_operations;
+        manager.memory.fill(self.fill_pattern);
+        manager
+    }
+}
 
-            // If there is leftover memory in the free block, add it back to free_blocks.
-            if block.size > size {
-                let leftover_block = MemoryBlock {
-                    start: block.start + size,
-                    size: block.size - size,
+impl Default for MemoryManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MemoryManager {
+    /// """Creates a new MemoryManager instance with the entire memory available as a single free block.
+    ///
+    /// Returns:
+    ///     MemoryManager: A new instance with initialized memory and free block tracking.
+    /// """
+    pub fn new() -> Self {
+        Self::with_capacity(MEMORY_SIZE)
+    }
+
+    /// """Creates a new MemoryManager managing `capacity` bytes, all available as a single free block.
+    ///
+    /// Args:
+    ///     capacity (usize): The total size of the managed memory in bytes.
+    ///
+    /// Returns:
+    ///     MemoryManager: A new instance with initialized memory and free block tracking.
+    /// """
+    pub fn with_capacity(capacity: usize) -> Self {
+        let mut free_map = BTreeMap::new();
+        if capacity > 0 {
+            free_map.insert(
+                capacity,
+                vec![MemoryBlock {
+                    start: 0,
+                    size: capacity,
                     allocated: false,
                     id: None,
-                };
-                self.free_blocks
-                    .entry(leftover_block.size)
-                    .or_insert_with(Vec::new)
-                    .push(leftover_block);
-            }
+                    created: 0,
+                    protected: false,
+                    tag: 0,
+                    generation: 0,
+                }],
+            );
+        }
 
-            return Some(new_id);
+        Self {
+            memory: vec![0; capacity],
+            free_blocks: free_map,
+            allocated_blocks: BTreeMap::new(),
+            by_address: BTreeSet::new(),
+            next_id: 0,
+            clock: 0,
+            generations: BTreeMap::new(),
+            operations: 0,
+            bytes_allocated_total: 0,
+            blocks_scanned: 0,
+            longest_scan: 0,
+            strict: false,
+            unknown_policy: UnknownPolicy::Warn,
+            radix: AddressRadix::Hex,
+            log_format: LogFormat::Human,
+            fill_pattern: 0,
+            zero_on_free: false,
+            strategy: AllocStrategy::BestFit,
+            coalesce_mode: CoalesceMode::Eager,
+            selector: None,
+            freed_stack: Vec::new(),
+            reserved: Vec::new(),
+            max_alloc_size: None,
+            auto_compact_every: None,
+            auto_compact_threshold: None,
+            max_total_operations: None,
+            growable: false,
+            #[cfg(feature = "test-hooks")]
+            forced_failures: 0,
         }
-        None
     }
 
-    /// """Frees an allocated memory block by its unique ID.
+    /// """Returns a builder for configuring a new MemoryManager.
     ///
-    /// This method removes the allocated block from the tracking map and re-adds it as a free block.
-    /// It prints an appropriate message based on whether the ID was found.
+    /// Returns:
+    ///     MemoryManagerBuilder: A builder with default settings.
+    /// """
+    pub fn builder() -> MemoryManagerBuilder {
+        MemoryManagerBuilder::default()
+    }
+
+    /// """Returns the manager to its initial state with all memory free.
     ///
-    /// Args:
-    ///     id (usize): The unique allocation ID of the block to be freed.
+    /// All allocations are discarded, memory is refilled with the fill pattern, the ID,
+    /// operation, allocated-bytes and scan counters restart from zero, and the entire memory becomes a single free block.
+    /// Settings such as strict mode and the address radix are kept, as are ranges withdrawn by
+    /// `reserve_reserved`, which split the free memory around them.
     ///
     /// Returns:
     ///     None
     /// """
-    fn delete(&mut self, id: usize) {
-        if let Some(block) = self.allocated_blocks.remove(&id) {
-            // Create a free block from the allocated block.
-            let free_block = MemoryBlock {
-                start: block.start,
-                size: block.size,
-                allocated: false,
-                id: None,
-            };
-            self.free_blocks
-                .entry(free_block.size)
-                .or_insert_with(Vec::new)
-                .push(free_block);
-            println!("Deleted ID: {}", id);
-        } else {
-            println!("Error: ID {} not found", id);
-        }
+    pub fn reset(&mut self) {
+        self.memory.fill(self.fill_pattern);
+        self.allocated_blocks.clear();
+        self.by_address.clear();
+        self.freed_stack.clear();
+        self.rebuild_free_list();
+        self.next_id = 0;
+        self.clock = 0;
+        self.operations = 0;
+        self.bytes_allocated_total = 0;
+        self.blocks_scanned = 0;
+        self.longest_scan = 0;
     }
 
-    /// """Finds the data associated with an allocated block by its unique ID.
+    /// """Sets the policy `insert` uses to choose a free block.
     ///
     /// Args:
-    ///     id (usize): The unique allocation ID to look up.
+    ///     strategy (AllocStrategy): The allocation strategy.
     ///
     /// Returns:
-    ///     Option<&[u8]>: A slice of the data stored in the allocated block if found, or None otherwise.
+    ///     None
     /// """
-    fn find(&self, id: usize) -> Option<&[u8]> {
-        self.allocated_blocks.get(&id).map(|block| {
-            &self.memory[block.start..block.start + block.size]
-        })
+    pub fn set_strategy(&mut self, strategy: AllocStrategy) {
+        self.strategy = strategy;
     }
 
-    /// """Reads and prints the data of an allocated block identified by its unique ID.
+    /// """Sets when freed blocks are merged with adjacent free space.
+    ///
+    /// Args:
+    ///     mode (CoalesceMode): The coalescing mode.
     ///
-    /// This method attempts to locate the allocated block and, if found, prints its data; otherwise,
-    /// it prints an error message.
+    /// Returns:
+    ///     None
+    /// """
+    pub fn set_coalesce_mode(&mut self, mode: CoalesceMode) {
+        self.coalesce_mode = mode;
+    }
+
+    /// """Sets or removes the largest size a single allocation may request.
     ///
     /// Args:
-    ///     id (usize): The unique allocation ID whose data should be printed.
+    ///     max (Option<usize>): The maximum allocation size in bytes, or None for no limit.
     ///
     /// Returns:
     ///     None
     /// """
-    fn read(&self, id: usize) {
-        match self.allocated_blocks.get(&id) {
-            Some(block) => {
-                let data = &self.memory[block.start..block.start + block.size];
-                println!("Data at ID {}: {:?}", id, data);
-            },
-            None => println!("Error: ID {} not found", id),
-        }
+    pub fn set_max_alloc_size(&mut self, max: Option<usize>) {
+        self.max_alloc_size = max;
     }
 
-    /// """Updates the data stored in an allocated block if the new data fits within the block.
+    /// """Installs a custom free-block selection policy used by `insert`.
     ///
-    /// The update occurs only if the length of the new data does not exceed the current allocated block size.
+    /// For each request, every free block that fits is collected in ascending address order
+    /// and passed to the selector, which picks one by index. This generalizes best-, first-
+    /// and worst-fit and takes precedence over the configured strategy. Returning None, or an
+    /// out-of-range index, makes the insert fail with `OutOfMemory`.
     ///
     /// Args:
-    ///     id (usize): The unique allocation ID of the block to update.
-    ///     new_data (&[u8]): A byte slice containing the new data.
+    ///     f (Selector): The selection function.
     ///
     /// Returns:
     ///     None
     /// """
-    fn update(&mut self, id: usize, new_data: &[u8]) {
-        if let Some(block) = self.allocated_blocks.get_mut(&id) {
-            if new_data.len() <= block.size {
-                self.memory[block.start..block.start + new_data.len()]
-                    .copy_from_slice(new_data);
-                println!("Updated ID: {} with new data {:?}", id, new_data);
-            } else {
-                println!("Error: New data exceeds allocated block size");
-            }
-        } else {
-            println!("Error: ID {} not found", id);
-        }
+    pub fn set_selector(&mut self, f: Selector) {
+        self.selector = Some(Arc::new(Mutex::new(f)));
     }
 
-    /// """Dumps the current state of memory, listing free and allocated blocks.
-    ///
-    /// This method prints all free blocks with their starting addresses and sizes,
-    /// followed by details of the currently allocated blocks.
+    /// """Removes any custom selector, restoring selection by the configured strategy.
     ///
     /// Returns:
     ///     None
     /// """
-    fn dump(&self) {
-        println!("Memory Dump:");
-        for (size, blocks) in &self.free_blocks {
-            for block in blocks {
-                println!("FREE: Start: {:#06x}, Size: {}", block.start, size);
-            }
-        }
-        for (id, block) in &self.allocated_blocks {
-            println!("ALLOCATED: ID: {}, Start: {:#06x}, Size: {}", id, block.start, block.size);
-        }
+    pub fn clear_selector(&mut self) {
+        self.selector = None;
     }
-}
 
-/// """Calculates the smallest power of two that is greater than or equal to a given request size.
-///
-/// This helper function is useful when ensuring that memory allocations are aligned or sized
+    /// """Restarts allocation IDs from 0.
+    ///
+    /// This is only allowed when nothing is allocated, since reusing the IDs of live blocks
+    /// would make lookups return the wrong allocation.
+    ///
+    /// Returns:
+    ///     Result<(), AllocError>: Ok(()) if the counter was reset, or `LiveAllocations` if any block is still allocated.
+    /// """
+    pub fn reset_ids(&mut self) -> Result<(), AllocError> {
+        if !self.allocated_blocks.is_empty() {
+            return Err(AllocError::LiveAllocations(self.allocated_blocks.len()));
+        }
+        self.next_id = 0;
+        Ok(())
+    }
+
+    /// """Returns the total size of the managed memory.
+    ///
+    /// Returns:
+    ///     usize: The capacity in bytes.
+    /// """
+    pub fn capacity(&self) -> usize {
+        self.memory.len()
+    }
+
+    /// """Returns the number of commands processed since creation or the last reset.
+    ///
+    /// Returns:
+    ///     usize: The operation count.
+    /// """
+    pub fn operation_count(&self) -> usize {
+        self.operations
+    }
+
+    /// """Records that one command has been processed.
+    ///
+    /// Returns:
+    ///     None
+    /// """
+    fn record_operation(&mut self) {
+        self.operations += 1;
+    }
+
+    /// """Returns how many more commands may be processed under the `max_total_operations` limit.
+    ///
+    /// Returns:
+    ///     Option<usize>: The commands left before processing stops, or None if there is no limit.
+    /// """
+    pub fn operations_remaining(&self) -> Option<usize> {
+        self.max_total_operations.map(|limit| limit.saturating_sub(self.operations))
+    }
+
+    /// """Compacts memory if the auto-compaction interval or threshold has been reached.
+    ///
+    /// Command processing calls this after each command. The interval is measured in
+    /// processed commands; the threshold only fires while some free space is fragmented.
+    ///
+    /// Returns:
+    ///     bool: True if `compact` ran, False otherwise.
+    /// """
+    fn auto_compact(&mut self) -> bool {
+        let interval_reached = self
+            .auto_compact_every
+            .is_some_and(|every| self.operations > 0 && self.operations.is_multiple_of(every));
+        // Compacting cannot go below the floor, so only fire while it would still help.
+        let threshold_reached = self.auto_compact_threshold.is_some_and(|threshold| {
+            let fragmentation = self.fragmentation();
+            fragmentation > self.fragmentation_floor() && fragmentation >= threshold
+        });
+        if !interval_reached && !threshold_reached {
+            return false;
+        }
+        self.compact();
+        true
+    }
+
+    /// """Enables or disables strict mode for command processing.
+    ///
+    /// In strict mode, a failed ASSERT command stops processing and returns an error naming
+    /// the failing line; otherwise the failure is reported and processing continues.
+    ///
+    /// Args:
+    ///     strict (bool): Whether strict mode should be enabled.
+    ///
+    /// Returns:
+    ///     None
+    /// """
+    pub fn set_strict(&mut self, strict: bool) {
+        self.strict = strict;
+    }
+
+    /// """Reports whether strict mode is enabled.
+    ///
+    /// Returns:
+    ///     bool: True if failed assertions stop command processing.
+    /// """
+    pub fn is_strict(&self) -> bool {
+        self.strict
+    }
+
+    /// """Sets what command processing does when DELETE names an ID that is not allocated.
+    ///
+    /// Args:
+    ///     policy (UnknownPolicy): Whether to warn and continue or to stop with an error.
+    ///
+    /// Returns:
+    ///     None
+    /// """
+    pub fn set_unknown_policy(&mut self, policy: UnknownPolicy) {
+        self.unknown_policy = policy;
+    }
+
+    /// """Returns what command processing does when DELETE names an ID that is not allocated.
+    ///
+    /// Returns:
+    ///     UnknownPolicy: The configured policy.
+    /// """
+    pub fn unknown_policy(&self) -> UnknownPolicy {
+        self.unknown_policy
+    }
+
+    /// """Sets the number base used when printing memory addresses.
+    ///
+    /// Args:
+    ///     radix (AddressRadix): The base to print addresses in.
+    ///
+    /// Returns:
+    ///     None
+    /// """
+    pub fn set_radix(&mut self, radix: AddressRadix) {
+        self.radix = radix;
+    }
+
+    /// """Sets how allocation and free events are written by command processing.
+    ///
+    /// Args:
+    ///     format (LogFormat): The event format.
+    ///
+    /// Returns:
+    ///     None
+    /// """
+    pub fn set_log_format(&mut self, format: LogFormat) {
+        self.log_format = format;
+    }
+
+    /// """Returns how allocation and free events are written by command processing.
+    ///
+    /// Returns:
+    ///     LogFormat: The event format.
+    /// """
+    pub fn log_format(&self) -> LogFormat {
+        self.log_format
+    }
+
+    /// """Formats a memory address using the configured radix.
+    ///
+    /// Args:
+    ///     address (usize): The address to format.
+    ///
+    /// Returns:
+    ///     String: The address in hexadecimal (e.g. `0x000a`) or decimal (e.g. `10`).
+    /// """
+    pub fn format_address(&self, address: usize) -> String {
+        match self.radix {
+            AddressRadix::Hex => format!("{:#06x}", address),
+            AddressRadix::Dec => address.to_string(),
+        }
+    }
+
+    /// """Forces the next `count` calls to `insert` to fail with `OutOfMemory`.
+    ///
+    /// Failures are injected regardless of available space; once `count` inserts have
+    /// failed, normal allocation resumes. Only available with the `test-hooks` feature.
+    ///
+    /// Args:
+    ///     count (usize): The number of upcoming inserts that should fail.
+    ///
+    /// Returns:
+    ///     None
+    /// """
+    #[cfg(feature = "test-hooks")]
+    pub fn fail_next_insert(&mut self, count: usize) {
+        self.forced_failures = count;
+    }
+
+    /// """Inserts data into memory using a best-fit allocation strategy.
+    ///
+    /// This method searches for the smallest free memory block that can accommodate the requested size.
+    /// If a suitable block is found, it allocates the block, writes the data into memory,
+    /// and adjusts free block tracking accordingly.
+    ///
+    /// Args:
+    ///     size (usize): The number of bytes to allocate.
+    ///     data (&[u8]): A byte slice containing the data to be stored.
+    ///
+    /// Returns:
+    ///     Result<usize, AllocError>: A unique allocation ID if the allocation is successful, `AllocationTooLarge`
+    ///     if `size` exceeds the configured maximum, or `OutOfMemory` if insufficient space is available.
+    /// """
+    pub fn insert(&mut self, size: usize, data: &[u8]) -> Result<usize, AllocError> {
+        self.check_alloc_size(size)?;
+        #[cfg(feature = "test-hooks")]
+        if self.forced_failures > 0 {
+            self.forced_failures -= 1;
+            return Err(AllocError::OutOfMemory { requested: size });
+        }
+
+        if let Some(block) = self.take_fitting_block(size) {
+            // Allocate and write data into memory.
+            let new_id = self.allocate_at(block.start, size, data);
+
+            // If there is leftover memory in the free block, add it back to free_blocks.
+            if block.size > size {
+                self.add_free_block(block.start + size, block.size - size);
+            }
+
+            return Ok(new_id);
+        }
+        if self.growable && self.grow_to_fit(size) {
+            return self.insert(size, data);
+        }
+        Err(AllocError::OutOfMemory { requested: size })
+    }
+
+    /// """Inserts data using its own length as the allocation size.
+    ///
+    /// This is the recommended way to store a full payload, since it rules out any
+    /// mismatch between the requested size and the length of the data.
+    ///
+    /// Args:
+    ///     data (&[u8]): A byte slice containing the data to be stored.
+    ///
+    /// Returns:
+    ///     Result<usize, AllocError>: A unique allocation ID, or `OutOfMemory` if insufficient space is available.
+    /// """
+    pub fn insert_data(&mut self, data: &[u8]) -> Result<usize, AllocError> {
+        self.insert(data.len(), data)
+    }
+
+    /// """Inserts data only into a free block whose size is close to the requested size.
+    ///
+    /// The smallest free block with a size in `[size, size + max_overhead]` is used. Larger
+    /// free blocks are never split, so the allocation fails if none qualifies even when
+    /// plenty of space is available, keeping a size class tightly packed.
+    ///
+    /// Args:
+    ///     size (usize): The number of bytes to allocate.
+    ///     max_overhead (usize): How many bytes larger than `size` the chosen free block may be.
+    ///     data (&[u8]): A byte slice containing the data to be stored.
+    ///
+    /// Returns:
+    ///     Result<usize, AllocError>: A unique allocation ID, or `OutOfMemory` if no free block is within the tolerance.
+    /// """
+    pub fn insert_within(&mut self, size: usize, max_overhead: usize, data: &[u8]) -> Result<usize, AllocError> {
+        self.check_alloc_size(size)?;
+        let key = self
+            .search_free_blocks(|manager| {
+                manager.free_blocks.range(size..=size.saturating_add(max_overhead)).next().map(|(&key, _)| key)
+            })
+            .ok_or(AllocError::OutOfMemory { requested: size })?;
+        let block = self.take_free_block(key, 0);
+
+        let new_id = self.allocate_at(block.start, size, data);
+        if block.size > size {
+            self.add_free_block(block.start + size, block.size - size);
+        }
+        Ok(new_id)
+    }
+
+    /// """Reserves a zero-filled block of the given size.
+    ///
+    /// Args:
+    ///     size (usize): The number of bytes to allocate.
+    ///
+    /// Returns:
+    ///     Result<usize, AllocError>: A unique allocation ID, or `OutOfMemory` if insufficient space is available.
+    /// """
+    pub fn reserve(&mut self, size: usize) -> Result<usize, AllocError> {
+        self.insert(size, &[])
+    }
+
+    /// """Inserts data at the high end of the highest-addressed free block that fits.
+    ///
+    /// Whereas `insert` grows allocations upward from the bottom of memory, this method
+    /// picks the fitting free block with the greatest starting address and carves the
+    /// allocation from its tail, so high allocations grow downward from the top.
+    ///
+    /// Args:
+    ///     size (usize): The number of bytes to allocate.
+    ///     data (&[u8]): A byte slice containing the data to be stored.
+    ///
+    /// Returns:
+    ///     Result<usize, AllocError>: The unique allocation ID, or `OutOfMemory` if no free block fits.
+    /// """
+    pub fn insert_high(&mut self, size: usize, data: &[u8]) -> Result<usize, AllocError> {
+        self.check_alloc_size(size)?;
+        let highest = |manager: &mut Self| {
+            // Track (size key, index in bucket, start) of the highest fitting block seen so far.
+            let mut chosen: Option<(usize, usize, usize)> = None;
+            for (&free_size, blocks) in manager.free_blocks.range(size..) {
+                for (index, block) in blocks.iter().enumerate() {
+                    if chosen.is_none_or(|(_, _, start)| block.start > start) {
+                        chosen = Some((free_size, index, block.start));
+                    }
+                }
+            }
+            chosen
+        };
+
+        let (key, index, _) = self.search_free_blocks(highest).ok_or(AllocError::OutOfMemory { requested: size })?;
+        let block = self.take_free_block(key, index);
+
+        // Allocate at the tail of the block and return the head to free_blocks.
+        let new_id = self.allocate_at(block.start + block.size - size, size, data);
+        if block.size > size {
+            self.add_free_block(block.start, block.size - size);
+        }
+        Ok(new_id)
+    }
+
+    /// """Chooses the free block `insert` should use for a request of `size` bytes.
+    ///
+    /// A custom selector takes precedence; otherwise the configured strategy decides.
+    ///
+    /// Args:
+    ///     size (usize): The number of bytes required.
+    ///
+    /// Returns:
+    ///     Option<(usize, usize)>: The size key and index within its bucket of the chosen block,
+    ///     or None if no suitable free block exists.
+    /// """
+    fn choose_free_block(&mut self, size: usize) -> Option<(usize, usize)> {
+        if self.selector.is_some() {
+            // The selector is offered every free block large enough.
+            self.blocks_scanned += self.free_blocks.range(size..).map(|(_, blocks)| blocks.len()).sum::<usize>();
+            return self.find_with_selector(size);
+        }
+        // Find the smallest free block (using BTreeMap range) that fits the requested size,
+        // unless the strategy prefers a recently freed block.
+        let recent = match self.strategy {
+            AllocStrategy::BestFit => None,
+            AllocStrategy::FirstFit => {
+                // Blocks are bucketed by size, so finding the lowest address examines every fitting block.
+                self.blocks_scanned += self.free_blocks.range(size..).map(|(_, blocks)| blocks.len()).sum::<usize>();
+                return self.find_lowest_fit(size, usize::MAX);
+            }
+            AllocStrategy::Lifo => self.pop_recently_freed(size),
+        };
+        recent.or_else(|| {
+            let chosen = self.find_best_fit(size);
+            // Every block in the range fits, so the scan stops at the first one it examines.
+            self.blocks_scanned += chosen.map_or(0, |(_, index)| index + 1);
+            chosen
+        })
+    }
+
+    /// """Finds the smallest free block that can hold `size` bytes.
+    ///
+    /// Args:
+    ///     size (usize): The number of bytes required.
+    ///
+    /// Returns:
+    ///     Option<(usize, usize)>: The size key and index within its bucket of the chosen block,
+    ///     or None if no free block is large enough.
+    /// """
+    fn find_best_fit(&self, size: usize) -> Option<(usize, usize)> {
+        best_fit_in(&self.free_blocks, size)
+    }
+
+    /// """Lets the custom selector choose among the free blocks that can hold `size` bytes.
+    ///
+    /// Args:
+    ///     size (usize): The number of bytes required.
+    ///
+    /// Returns:
+    ///     Option<(usize, usize)>: The size key and index within its bucket of the chosen block,
+    ///     or None if there is no selector or it declined.
+    /// """
+    fn find_with_selector(&self, size: usize) -> Option<(usize, usize)> {
+        let selector = self.selector.as_ref()?;
+        let mut candidates: Vec<(CandidateBlock, usize, usize)> = self
+            .free_blocks
+            .range(size..)
+            .flat_map(|(&key, blocks)| {
+                blocks.iter().enumerate().map(move |(index, block)| {
+                    let candidate = CandidateBlock {
+                        start: block.start,
+                        size: block.size,
+                    };
+                    (candidate, key, index)
+                })
+            })
+            .collect();
+        candidates.sort_unstable_by_key(|(candidate, _, _)| candidate.start);
+
+        let offered: Vec<CandidateBlock> = candidates.iter().map(|(candidate, _, _)| *candidate).collect();
+        // A selector that panicked left nothing half-updated, so a poisoned lock is still usable.
+        let choice = (selector.lock().unwrap_or_else(|poisoned| poisoned.into_inner()))(&offered)?;
+        candidates.get(choice).map(|&(_, key, index)| (key, index))
+    }
+
+    /// """Extends memory just enough that a single free block at the top can hold `size` bytes.
+    ///
+    /// Any free blocks that already run up to the top of memory are absorbed into the new
+    /// block, so memory only grows by the shortfall. Memory never grows past
+    /// `MAX_GROWN_CAPACITY` bytes.
+    ///
+    /// Args:
+    ///     size (usize): The number of bytes the top free block must hold.
+    ///
+    /// Returns:
+    ///     bool: True if memory now ends in a large enough free block, False (with nothing
+    ///     changed) if that would exceed `MAX_GROWN_CAPACITY`.
+    /// """
+    fn grow_to_fit(&mut self, size: usize) -> bool {
+        let capacity = self.capacity();
+        // Find the start of the run of free blocks that ends at the top of memory.
+        let mut start = capacity;
+        while let Some((key, index)) = self.free_block_ending_at(start) {
+            start = self.free_blocks[&key][index].start;
+        }
+        let new_capacity = match start.checked_add(size) {
+            Some(end) if end <= MAX_GROWN_CAPACITY => capacity.max(end),
+            _ => return false,
+        };
+
+        // Absorb that run into the new top block.
+        let mut end = capacity;
+        while end > start {
+            let (key, index) = self.free_block_ending_at(end).unwrap();
+            end = self.take_free_block(key, index).start;
+        }
+        self.memory.resize(new_capacity, self.fill_pattern);
+        self.add_free_block(start, new_capacity - start);
+        true
+    }
+
+    /// """Finds the free block whose region ends exactly at `end`.
+    ///
+    /// Args:
+    ///     end (usize): The address one past the block's last byte.
+    ///
+    /// Returns:
+    ///     Option<(usize, usize)>: The size key and index within its bucket of the block, if any.
+    /// """
+    fn free_block_ending_at(&self, end: usize) -> Option<(usize, usize)> {
+        self.free_blocks.iter().find_map(|(&key, blocks)| {
+            blocks
+                .iter()
+                .position(|block| block.size > 0 && block.start + block.size == end)
+                .map(|index| (key, index))
+        })
+    }
+
+    /// """Finds the non-empty free block that starts exactly at `start`.
+    ///
+    /// Args:
+    ///     start (usize): The starting address to look for.
+    ///
+    /// Returns:
+    ///     Option<(usize, usize)>: The size key and index within its bucket of the block, if any.
+    /// """
+    fn free_block_starting_at(&self, start: usize) -> Option<(usize, usize)> {
+        self.free_blocks.range(1..).find_map(|(&key, blocks)| {
+            blocks
+                .binary_search_by_key(&start, |block| block.start)
+                .ok()
+                .map(|index| (key, index))
+        })
+    }
+
+    /// """Removes a free block from its size bucket, dropping the bucket if it becomes empty.
+    ///
+    /// Args:
+    ///     key (usize): The size key of the bucket holding the block.
+    ///     index (usize): The position of the block within the bucket.
+    ///
+    /// Returns:
+    ///     MemoryBlock: The removed free block.
+    /// """
+    fn take_free_block(&mut self, key: usize, index: usize) -> MemoryBlock {
+        let blocks = self.free_blocks.get_mut(&key).unwrap();
+        let block = blocks.remove(index);
+        // Clean up the entry if no more blocks exist for that key.
+        if blocks.is_empty() {
+            self.free_blocks.remove(&key);
+        }
+        block
+    }
+
+    /// """Adds a free block covering the given region to free_blocks.
+    ///
+    /// The block is inserted at its address-ordered position within its size bucket, so
+    /// blocks of equal size are always chosen lowest address first.
+    ///
+    /// Args:
+    ///     start (usize): The starting index of the free region.
+    ///     size (usize): The size of the free region in bytes.
+    ///
+    /// Returns:
+    ///     None
+    /// """
+    fn add_free_block(&mut self, start: usize, size: usize) {
+        let blocks = self.free_blocks.entry(size).or_default();
+        let index = blocks.partition_point(|block| block.start < start);
+        blocks.insert(
+            index,
+            MemoryBlock {
+                start,
+                size,
+                allocated: false,
+                id: None,
+                created: 0,
+                protected: false,
+                tag: 0,
+                generation: 0,
+            },
+        );
+    }
+
+    /// """Returns a region that was allocated to the free list.
+    ///
+    /// When `zero_on_free` is enabled the region is first overwritten with the fill pattern.
+    /// In eager coalescing mode the region is merged with any free blocks directly below and
+    /// above it. Under the `Lifo` strategy the resulting block is also recorded as the most
+    /// recently freed.
+    ///
+    /// Args:
+    ///     start (usize): The starting index of the freed region.
+    ///     size (usize): The size of the freed region in bytes.
+    ///
+    /// Returns:
+    ///     None
+    /// """
+    fn release_region(&mut self, mut start: usize, mut size: usize) {
+        // A zero-size allocation frees no memory; filing it would leave an empty free block.
+        if size == 0 {
+            return;
+        }
+        if self.zero_on_free {
+            self.memory[start..start + size].fill(self.fill_pattern);
+        }
+        if self.coalesce_mode == CoalesceMode::Eager {
+            if let Some((key, index)) = self.free_block_ending_at(start) {
+                let below = self.take_free_block(key, index);
+                start = below.start;
+                size += below.size;
+            }
+            if let Some((key, index)) = self.free_block_starting_at(start + size) {
+                size += self.take_free_block(key, index).size;
+            }
+        }
+        self.add_free_block(start, size);
+        if self.strategy == AllocStrategy::Lifo {
+            self.freed_stack.push((start, size));
+        }
+    }
+
+    /// """Pops the most recently freed block that can hold `size` bytes.
+    ///
+    /// Entries whose block has since been allocated or merged are discarded first, since
+    /// the recorded region is no longer a free block.
+    ///
+    /// Args:
+    ///     size (usize): The number of bytes required.
+    ///
+    /// Returns:
+    ///     Option<(usize, usize)>: The size key and index within its bucket of the chosen block,
+    ///     or None if no recently freed block is large enough.
+    /// """
+    fn pop_recently_freed(&mut self, size: usize) -> Option<(usize, usize)> {
+        let free_blocks = &self.free_blocks;
+        self.freed_stack.retain(|&(start, block_size)| {
+            free_blocks
+                .get(&block_size)
+                .is_some_and(|blocks| blocks.iter().any(|block| block.start == start))
+        });
+
+        let position = self.freed_stack.iter().rposition(|&(_, block_size)| block_size >= size);
+        self.blocks_scanned += position.map_or(self.freed_stack.len(), |position| self.freed_stack.len() - position);
+        let position = position?;
+        let (start, block_size) = self.freed_stack.remove(position);
+        let index = self.free_blocks[&block_size]
+            .iter()
+            .position(|block| block.start == start)?;
+        Some((block_size, index))
+    }
+
+    /// """Rejects a request larger than the configured maximum allocation size.
+    ///
+    /// Args:
+    ///     size (usize): The requested size in bytes.
+    ///
+    /// Returns:
+    ///     Result<(), AllocError>: Ok(()) if the size is allowed, or `AllocationTooLarge` otherwise.
+    /// """
+    fn check_alloc_size(&self, size: usize) -> Result<(), AllocError> {
+        match self.max_alloc_size {
+            Some(max) if size > max => Err(AllocError::AllocationTooLarge { requested: size, max }),
+            _ => Ok(()),
+        }
+    }
+
+    /// """Runs a free-list search, retrying once after merging free neighbours in lazy mode.
+    ///
+    /// In lazy coalescing mode merging is deferred, so a search can miss space that is free
+    /// but still split into adjacent blocks. Every allocating method searches through this so
+    /// none of them reports `OutOfMemory` while a merge would have satisfied it.
+    ///
+    /// Args:
+    ///     search (FnMut(&mut MemoryManager) -> Option<T>): Looks for a suitable free block.
+    ///
+    /// Returns:
+    ///     Option<T>: The result of the first successful search, or None if nothing fits even after merging.
+    /// """
+    fn search_free_blocks<T>(&mut self, mut search: impl FnMut(&mut Self) -> Option<T>) -> Option<T> {
+        let found = search(self);
+        if found.is_none() && self.coalesce_mode == CoalesceMode::Lazy && self.coalesce() > 0 {
+            return search(self);
+        }
+        found
+    }
+
+    /// """Removes the free block `insert` would use for a request from the free list.
+    ///
+    /// In lazy coalescing mode a failed search merges free neighbours and tries again.
+    ///
+    /// Args:
+    ///     size (usize): The number of bytes needed.
+    ///
+    /// Returns:
+    ///     Option<MemoryBlock>: The whole chosen free block, or None if nothing fits.
+    /// """
+    fn take_fitting_block(&mut self, size: usize) -> Option<MemoryBlock> {
+        let scanned_before = self.blocks_scanned;
+        let chosen = self.search_free_blocks(|manager| manager.choose_free_block(size));
+        self.longest_scan = self.longest_scan.max(self.blocks_scanned - scanned_before);
+        let (key, index) = chosen?;
+        Some(self.take_free_block(key, index))
+    }
+
+    /// """Records a new allocation at `start` and writes its data into memory.
+    ///
+    /// At most `size` bytes of `data` are copied; if `data` is shorter than `size`,
+    /// the remainder of the block is zeroed.
+    ///
+    /// Args:
+    ///     start (usize): The starting index of the allocation.
+    ///     size (usize): The size of the allocation in bytes.
+    ///     data (&[u8]): A byte slice containing the data to be stored.
+    ///
+    /// Returns:
+    ///     usize: The unique ID assigned to the new allocation.
+    /// """
+    fn allocate_at(&mut self, start: usize, size: usize, data: &[u8]) -> usize {
+        let new_id = self.next_id;
+        self.next_id += 1;
+        self.bytes_allocated_total += size;
+        let created = self.clock;
+        self.clock += 1;
+        let generation = self.issue_generation(new_id);
+
+        // Ensure we copy only up to 'size' bytes.
+        let len = size.min(data.len());
+        self.memory[start..start + len].copy_from_slice(&data[..len]);
+        self.memory[start + len..start + size].fill(0);
+
+        self.by_address.insert((start, new_id));
+        self.allocated_blocks.insert(
+            new_id,
+            MemoryBlock {
+                start,
+                size,
+                allocated: true,
+                id: Some(new_id),
+                created,
+                protected: false,
+                tag: 0,
+                generation,
+            },
+        );
+        new_id
+    }
+
+    /// """Records that an ID is being issued and returns its generation.
+    ///
+    /// Args:
+    ///     id (usize): The ID being issued.
+    ///
+    /// Returns:
+    ///     u32: 0 the first time the ID is issued, incremented on every reissue.
+    /// """
+    fn issue_generation(&mut self, id: usize) -> u32 {
+        *self.generations.entry(id).and_modify(|generation| *generation += 1).or_insert(0)
+    }
+
+    /// """Frees an allocated memory block by its unique ID.
+    ///
+    /// This method removes the allocated block from the tracking map and re-adds it as a free block.
+    ///
+    /// Args:
+    ///     id (usize): The unique allocation ID of the block to be freed.
+    ///
+    /// Returns:
+    ///     Result<(), AllocError>: Ok(()) if the block was freed, or `UnknownId` if the ID is not allocated.
+    /// """
+    pub fn delete(&mut self, id: usize) -> Result<(), AllocError> {
+        let block = self.allocated_blocks.remove(&id).ok_or(AllocError::UnknownId(id))?;
+        self.by_address.remove(&(block.start, id));
+        // Create a free block from the allocated block.
+        self.release_region(block.start, block.size);
+        Ok(())
+    }
+
+    /// """Frees an allocated memory block and returns a copy of the data it held.
+    ///
+    /// Args:
+    ///     id (usize): The unique allocation ID of the block to be freed.
+    ///
+    /// Returns:
+    ///     Option<Vec<u8>>: The block's data as it was before freeing, or None if the ID is not allocated.
+    /// """
+    pub fn delete_returning(&mut self, id: usize) -> Option<Vec<u8>> {
+        let data = self.find(id)?.to_vec();
+        self.delete(id).ok()?;
+        Some(data)
+    }
+
+    /// """Finds the data associated with an allocated block by its unique ID.
+    ///
+    /// Args:
+    ///     id (usize): The unique allocation ID to look up.
+    ///
+    /// Returns:
+    ///     Option<&[u8]>: A slice of the data stored in the allocated block if found, or None otherwise.
+    /// """
+    pub fn find(&self, id: usize) -> Option<&[u8]> {
+        self.find_with_addr(id).map(|(_, data)| data)
+    }
+
+    /// """Finds the starting address and data of an allocated block by its unique ID.
+    ///
+    /// Args:
+    ///     id (usize): The unique allocation ID to look up.
+    ///
+    /// Returns:
+    ///     Option<(usize, &[u8])>: The block's starting address and a slice of its data if found, or None otherwise.
+    /// """
+    pub fn find_with_addr(&self, id: usize) -> Option<(usize, &[u8])> {
+        self.allocated_blocks.get(&id).map(|block| {
+            (block.start, &self.memory[block.start..block.start + block.size])
+        })
+    }
+
+    /// """Updates the data stored in an allocated block if the new data fits within the block.
+    ///
+    /// The update occurs only if the length of the new data does not exceed the current allocated block size.
+    ///
+    /// Args:
+    ///     id (usize): The unique allocation ID of the block to update.
+    ///     new_data (&[u8]): A byte slice containing the new data.
+    ///
+    /// Returns:
+    ///     Result<(), AllocError>: Ok(()) if the data was written, `UnknownId` if the ID is not allocated,
+    ///     `Protected` if the block is write-protected, or `DataTooLarge` if the new data exceeds the block size.
+    /// """
+    pub fn update(&mut self, id: usize, new_data: &[u8]) -> Result<(), AllocError> {
+        let block = self.allocated_blocks.get(&id).ok_or(AllocError::UnknownId(id))?;
+        if block.protected {
+            return Err(AllocError::Protected(id));
+        }
+        if new_data.len() > block.size {
+            return Err(AllocError::DataTooLarge {
+                len: new_data.len(),
+                size: block.size,
+            });
+        }
+        self.memory[block.start..block.start + new_data.len()].copy_from_slice(new_data);
+        Ok(())
+    }
+
+    /// """Replaces the whole contents of an allocated block.
+    ///
+    /// Unlike `update`, which accepts shorter data and leaves the tail untouched, the new data
+    /// must be exactly the block's size, so on success every byte of the block was rewritten.
+    ///
+    /// Args:
+    ///     id (usize): The unique allocation ID of the block to replace.
+    ///     new_data (&[u8]): The block's new contents.
+    ///
+    /// Returns:
+    ///     Result<(), AllocError>: Ok(()) if the data was written, `UnknownId` if the ID is not allocated,
+    ///     `Protected` if the block is write-protected, or `SizeMismatch` if the data length differs from the block size.
+    /// """
+    pub fn replace(&mut self, id: usize, new_data: &[u8]) -> Result<(), AllocError> {
+        let block = self.allocated_blocks.get(&id).ok_or(AllocError::UnknownId(id))?;
+        if block.protected {
+            return Err(AllocError::Protected(id));
+        }
+        if new_data.len() != block.size {
+            return Err(AllocError::SizeMismatch {
+                len: new_data.len(),
+                size: block.size,
+            });
+        }
+        self.memory[block.start..block.start + block.size].copy_from_slice(new_data);
+        Ok(())
+    }
+
+    /// """Marks an allocation as read-only.
+    ///
+    /// The data already stored stays in place; later writes through `update` fail with
+    /// `Protected` until `unprotect` is called. Freeing the block is still allowed.
+    ///
+    /// Args:
+    ///     id (usize): The unique allocation ID of the block to protect.
+    ///
+    /// Returns:
+    ///     Result<(), AllocError>: Ok(()) if the block is now protected, or `UnknownId` if the ID is not allocated.
+    /// """
+    pub fn protect(&mut self, id: usize) -> Result<(), AllocError> {
+        let block = self.allocated_blocks.get_mut(&id).ok_or(AllocError::UnknownId(id))?;
+        block.protected = true;
+        Ok(())
+    }
+
+    /// """Allows writes to a previously protected allocation again.
+    ///
+    /// Args:
+    ///     id (usize): The unique allocation ID of the block to unprotect.
+    ///
+    /// Returns:
+    ///     Result<(), AllocError>: Ok(()) if the block is writable, or `UnknownId` if the ID is not allocated.
+    /// """
+    pub fn unprotect(&mut self, id: usize) -> Result<(), AllocError> {
+        let block = self.allocated_blocks.get_mut(&id).ok_or(AllocError::UnknownId(id))?;
+        block.protected = false;
+        Ok(())
+    }
+
+    /// """Dumps the current state of memory, listing free and allocated blocks.
+    ///
+    /// This method writes all free blocks with their starting addresses and sizes,
+    /// followed by details of the currently allocated blocks.
+    ///
+    /// Args:
+    ///     out (&mut dyn Write): The sink the dump is written to.
+    ///
+    /// Returns:
+    ///     io::Result<()>: Ok(()) on success, or the error returned by the sink.
+    /// """
+    pub fn dump_to(&self, out: &mut dyn Write) -> io::Result<()> {
+        writeln!(out, "Memory Dump:")?;
+        for (size, blocks) in &self.free_blocks {
+            for block in blocks {
+                writeln!(out, "FREE: Start: {}, Size: {}", self.format_address(block.start), size)?;
+            }
+        }
+        for (id, block) in &self.allocated_blocks {
+            writeln!(
+                out,
+                "ALLOCATED: ID: {}, Start: {}, Size: {}",
+                id,
+                self.format_address(block.start),
+                block.size
+            )?;
+        }
+        for &(start, size) in &self.reserved {
+            writeln!(out, "RESERVED: Start: {}, Size: {}", self.format_address(start), size)?;
+        }
+        Ok(())
+    }
+
+    /// """Reports the 50th, 90th and 99th percentile sizes of the current free blocks.
+    ///
+    /// Percentiles use the nearest-rank method over every block in the free list, so each
+    /// returned value is the size of an actual free block. An empty free list yields zeros.
+    ///
+    /// Returns:
+    ///     (usize, usize, usize): The (p50, p90, p99) free block sizes in bytes.
+    /// """
+    pub fn free_block_percentiles(&self) -> (usize, usize, usize) {
+        // free_blocks is keyed by size, so this list is already in ascending order.
+        let sizes: Vec<usize> = self
+            .free_blocks
+            .iter()
+            .flat_map(|(&size, blocks)| std::iter::repeat_n(size, blocks.len()))
+            .collect();
+        if sizes.is_empty() {
+            return (0, 0, 0);
+        }
+
+        let rank = |percent: usize| {
+            let index = (percent * sizes.len()).div_ceil(100).max(1) - 1;
+            sizes[index]
+        };
+        (rank(50), rank(90), rank(99))
+    }
+
+    /// """Merges every run of address-adjacent free blocks into a single free block.
+    ///
+    /// In lazy coalescing mode, deleting a block does not merge it with its neighbours, so
+    /// free space fragments into many small blocks over time. This performs one full pass over
+    /// the free list and rebuilds it with each contiguous free region represented by a single block.
+    ///
+    /// Returns:
+    ///     usize: The number of merges performed (free blocks before minus free blocks after).
+    /// """
+    pub fn coalesce(&mut self) -> usize {
+        self.coalesce_report().iter().map(|&(_, _, blocks)| blocks - 1).sum()
+    }
+
+    /// """Coalesces like `coalesce` and describes each group of blocks that was merged.
+    ///
+    /// Regions that were already a single free block are left out, so an empty report means
+    /// the free list had nothing to merge.
+    ///
+    /// Returns:
+    ///     Vec<(usize, usize, usize)>: The (merged_start, combined_size, num_blocks_merged) of each
+    ///     merged region in ascending address order.
+    /// """
+    pub fn coalesce_report(&mut self) -> Vec<(usize, usize, usize)> {
+        let mut groups: Vec<(usize, usize, usize)> = Vec::new();
+        for (start, size) in self.free_blocks_ordered() {
+            match groups.last_mut() {
+                Some((last_start, last_size, blocks)) if *last_start + *last_size == start => {
+                    *last_size += size;
+                    *blocks += 1;
+                }
+                _ => groups.push((start, size, 1)),
+            }
+        }
+
+        self.free_blocks.clear();
+        for &(start, size, _) in &groups {
+            self.add_free_block(start, size);
+        }
+        groups.retain(|&(_, _, blocks)| blocks > 1);
+        groups
+    }
+
+    /// """Lists every free block as a (start, size) pair in ascending address order.
+    ///
+    /// Returns:
+    ///     Vec<(usize, usize)>: The free blocks sorted by starting address.
+    /// """
+    fn free_blocks_ordered(&self) -> Vec<(usize, usize)> {
+        let mut ordered: Vec<(usize, usize)> = self
+            .free_blocks
+            .values()
+            .flatten()
+            .map(|block| (block.start, block.size))
+            .collect();
+        ordered.sort_unstable();
+        ordered
+    }
+
+    /// """Reports the natural alignment of an allocated block.
+    ///
+    /// The natural alignment is the largest power of two that divides the block's starting
+    /// address. A block starting at address 0 is aligned to every power of two, so the
+    /// largest representable power of two is returned for it.
+    ///
+    /// Args:
+    ///     id (usize): The unique allocation ID to inspect.
+    ///
+    /// Returns:
+    ///     Option<usize>: The block's alignment in bytes, or None if the ID is not allocated.
+    /// """
+    pub fn alignment_of(&self, id: usize) -> Option<usize> {
+        self.allocated_blocks
+            .get(&id)
+            .map(|block| 1 << block.start.trailing_zeros().min(usize::BITS - 1))
+    }
+
+    /// """Summarizes current memory usage and fragmentation.
+    ///
+    /// Returns:
+    ///     MemoryStats: Byte and block counts for allocated and free memory, including the
+    ///     largest contiguous free region now and after compaction, and the free space only
+    ///     compaction could make usable.
+    /// """
+    pub fn stats(&self) -> MemoryStats {
+        let used_bytes = self.allocated_blocks.values().map(|block| block.size).sum();
+        let free_bytes = self.free_blocks.values().flatten().map(|block| block.size).sum();
+        let largest_gap = self
+            .free_intervals()
+            .into_iter()
+            .map(|(start, end)| end - start)
+            .max()
+            .unwrap_or(0);
+
+        MemoryStats {
+            total_bytes: self.capacity(),
+            used_bytes,
+            free_bytes,
+            allocation_count: self.allocated_blocks.len(),
+            free_block_count: self.free_blocks.values().map(Vec::len).sum(),
+            largest_gap,
+            reclaimable_by_compaction: free_bytes - largest_gap,
+            largest_gap_after_compaction: self.compacted_gaps().into_iter().map(|(_, size)| size).max().unwrap_or(0),
+            operation_count: self.operations,
+            operations_remaining: self.operations_remaining(),
+            bytes_allocated_total: self.bytes_allocated_total,
+            blocks_scanned: self.blocks_scanned,
+            longest_scan: self.longest_scan,
+        }
+    }
+
+    /// """Lists contiguous free regions in ascending address order.
+    ///
+    /// Address-adjacent free blocks are reported as one region without modifying the free list.
+    ///
+    /// Returns:
+    ///     Vec<(usize, usize)>: The (start, size) of each contiguous free region.
+    /// """
+    fn merged_free_regions(&self) -> Vec<(usize, usize)> {
+        let mut merged: Vec<(usize, usize)> = Vec::new();
+        for (start, size) in self.free_blocks_ordered() {
+            match merged.last_mut() {
+                Some((last_start, last_size)) if *last_start + *last_size == start => {
+                    *last_size += size;
+                }
+                _ => merged.push((start, size)),
+            }
+        }
+        merged
+    }
+
+    /// """Returns free memory as disjoint intervals in ascending address order.
+    ///
+    /// Address-adjacent free blocks are merged, so the result is the same whether or not
+    /// the free list itself has been coalesced. This is the canonical view of free space
+    /// for callers building their own placement policies.
+    ///
+    /// Returns:
+    ///     Vec<(usize, usize)>: The half-open (start, end) range of each free interval.
+    /// """
+    pub fn free_intervals(&self) -> Vec<(usize, usize)> {
+        self.merged_free_regions()
+            .into_iter()
+            .map(|(start, size)| (start, start + size))
+            .collect()
+    }
+
+    /// """Lists free gaps too small to satisfy requests of a given size.
+    ///
+    /// Gaps are contiguous free regions, so address-adjacent free blocks are counted
+    /// together. Summing the sizes gives the amount of free space that is effectively dead
+    /// for requests of at least `below` bytes.
+    ///
+    /// Args:
+    ///     below (usize): Gaps strictly smaller than this many bytes are reported.
+    ///
+    /// Returns:
+    ///     Vec<(usize, usize)>: The (start, size) of each such gap in ascending address order.
+    /// """
+    pub fn slivers(&self, below: usize) -> Vec<(usize, usize)> {
+        self.merged_free_regions()
+            .into_iter()
+            .filter(|&(_, size)| size < below)
+            .collect()
+    }
+
+    /// """Measures how fragmented the free space is.
+    ///
+    /// Fragmentation is the share of free bytes that lie outside the largest contiguous free
+    /// region: 0.0 means all free space is one contiguous region, and values approaching 1.0
+    /// mean free space is scattered across many small gaps. With no free space it is 0.0.
+    ///
+    /// Returns:
+    ///     f64: The fragmentation ratio between 0.0 and 1.0.
+    /// """
+    pub fn fragmentation(&self) -> f64 {
+        self.stats().fragmentation()
+    }
+
+    /// """Reports the lowest fragmentation any rearrangement of the current allocations could reach.
+    ///
+    /// This is the fragmentation `compact` would leave. Without reserved ranges compaction
+    /// leaves the free space as one region, so the floor is 0.0; a reserved range can strand a
+    /// gap below it, which raises the floor. With no free space the floor is 0.0 rather than
+    /// 1.0, matching `fragmentation`, which treats a full arena as unfragmented. STATS prints
+    /// the floor next to the current fragmentation, along with the largest free region
+    /// compaction would leave.
+    ///
+    /// Returns:
+    ///     f64: The best achievable fragmentation ratio.
+    /// """
+    pub fn fragmentation_floor(&self) -> f64 {
+        self.stats().fragmentation_floor()
+    }
+
+    /// """Slides every allocation toward the low end of memory, leaving one free block at the top.
+    ///
+    /// Allocations keep their IDs and relative address order; only their starting
+    /// addresses change, so any cached addresses are invalidated. Reserved ranges stay put
+    /// and allocations are packed around them, so with reserved ranges a free gap can also
+    /// remain below each range where the next allocation did not fit.
+    ///
+    /// Returns:
+    ///     None
+    /// """
+    pub fn compact(&mut self) {
+        let mut by_address: Vec<&mut MemoryBlock> = self.allocated_blocks.values_mut().collect();
+        by_address.sort_unstable_by_key(|block| block.start);
+
+        let mut cursor = 0;
+        for block in by_address {
+            cursor = skip_reserved_up(&self.reserved, cursor, block.size);
+            if block.start != cursor {
+                self.memory.copy_within(block.start..block.start + block.size, cursor);
+                block.start = cursor;
+            }
+            cursor += block.size;
+        }
+        self.rebuild_address_index();
+        self.rebuild_free_list();
+    }
+
+    /// """Slides every allocation toward the high end of memory, leaving one free block at address 0.
+    ///
+    /// This mirrors `compact` for layouts that keep the dynamic pool against the top of
+    /// memory. Allocations keep their IDs and relative address order; only their starting
+    /// addresses change, so any cached addresses are invalidated. Reserved ranges stay put
+    /// and allocations are packed around them.
+    ///
+    /// Returns:
+    ///     None
+    /// """
+    pub fn compact_high(&mut self) {
+        let mut by_address: Vec<&mut MemoryBlock> = self.allocated_blocks.values_mut().collect();
+        by_address.sort_unstable_by_key(|block| std::cmp::Reverse(block.start));
+
+        let mut cursor = self.memory.len();
+        for block in by_address {
+            cursor = skip_reserved_down(&self.reserved, cursor, block.size) - block.size;
+            if block.start != cursor {
+                self.memory.copy_within(block.start..block.start + block.size, cursor);
+                block.start = cursor;
+            }
+        }
+        self.rebuild_address_index();
+        self.rebuild_free_list();
+    }
+
+    /// """Compacts memory, placing allocations in order of creation rather than address.
+    ///
+    /// The oldest allocation ends up at address 0 and the newest just below the single free
+    /// block left at the top. When long-lived blocks tend to be the oldest, clustering them
+    /// low keeps churn from newer blocks away from them. IDs are unchanged, but cached
+    /// addresses are invalidated. Reserved ranges stay put and allocations are packed around them.
+    /// Packed in age order, the allocations may not fit around the reserved ranges; memory is
+    /// then compacted in address order as `compact` does, which always fits.
+    ///
+    /// Returns:
+    ///     None
+    /// """
+    pub fn compact_by_age(&mut self) {
+        let mut by_age: Vec<&mut MemoryBlock> = self.allocated_blocks.values_mut().collect();
+        by_age.sort_unstable_by_key(|block| block.created);
+
+        // Plan every destination first so nothing moves if the layout does not fit.
+        let mut destinations = Vec::with_capacity(by_age.len());
+        let mut cursor = 0;
+        for block in &by_age {
+            cursor = skip_reserved_up(&self.reserved, cursor, block.size);
+            destinations.push(cursor);
+            cursor += block.size;
+        }
+        if cursor > self.memory.len() {
+            self.compact();
+            return;
+        }
+
+        // Blocks are not moved in address order, so a destination may overlap a block that
+        // has not been moved yet; copy out of a snapshot instead of in place.
+        let snapshot = self.memory.clone();
+        for (block, destination) in by_age.into_iter().zip(destinations) {
+            self.memory[destination..destination + block.size].copy_from_slice(&snapshot[block.start..block.start + block.size]);
+            block.start = destination;
+        }
+        self.rebuild_address_index();
+        self.rebuild_free_list();
+    }
+
+    /// """Rebuilds the address index after allocations have been moved in bulk.
+    ///
+    /// Returns:
+    ///     None
+    /// """
+    fn rebuild_address_index(&mut self) {
+        self.by_address = self.allocated_blocks.iter().map(|(&id, block)| (block.start, id)).collect();
+    }
+
+    /// """Records that an allocation now starts at a new address.
+    ///
+    /// Args:
+    ///     id (usize): The unique allocation ID of the moved block.
+    ///     new_start (usize): The block's new starting address.
+    ///
+    /// Returns:
+    ///     None
+    /// """
+    fn set_start(&mut self, id: usize, new_start: usize) {
+        let block = self.allocated_blocks.get_mut(&id).unwrap();
+        self.by_address.remove(&(block.start, id));
+        self.by_address.insert((new_start, id));
+        block.start = new_start;
+    }
+
+    /// """Rebuilds the free list from the memory not covered by allocations or reserved ranges.
+    ///
+    /// Each uncovered gap becomes one free block, released as a freed region would be.
+    ///
+    /// Returns:
+    ///     None
+    /// """
+    fn rebuild_free_list(&mut self) {
+        let covered = self
+            .allocated_blocks
+            .values()
+            .map(|block| (block.start, block.size))
+            .chain(self.reserved.iter().copied())
+            .collect();
+        self.free_blocks.clear();
+        for (start, size) in uncovered_gaps(covered, self.capacity()) {
+            self.release_region(start, size);
+        }
+    }
+
+    /// """Predicts the free gaps `compact` would leave, without moving anything.
+    ///
+    /// Without reserved ranges this is at most one gap at the top of memory; each reserved
+    /// range can leave another gap below it that the next allocation did not fit into.
+    ///
+    /// Returns:
+    ///     Vec<(usize, usize)>: The (start, size) of each free gap after compaction, in address order.
+    /// """
+    fn compacted_gaps(&self) -> Vec<(usize, usize)> {
+        let mut sizes: Vec<(usize, usize)> = self.allocated_blocks.values().map(|block| (block.start, block.size)).collect();
+        sizes.sort_unstable();
+
+        let mut covered = self.reserved.clone();
+        let mut cursor = 0;
+        for (_, size) in sizes {
+            cursor = skip_reserved_up(&self.reserved, cursor, size);
+            covered.push((cursor, size));
+            cursor += size;
+        }
+        uncovered_gaps(covered, self.capacity())
+    }
+
+    /// """Compacts memory only when fragmentation has reached a threshold.
+    ///
+    /// Args:
+    ///     threshold (f64): The minimum `fragmentation()` ratio that triggers compaction.
+    ///
+    /// Returns:
+    ///     bool: True if `compact` ran (and allocation addresses may have moved), False otherwise.
+    /// """
+    pub fn compact_if_fragmented(&mut self, threshold: f64) -> bool {
+        if self.fragmentation() < threshold {
+            return false;
+        }
+        self.compact();
+        true
+    }
+
+    /// """Copies every allocation of another manager into this one.
+    ///
+    /// Allocations are imported in ascending ID order using the normal best-fit `insert`.
+    /// IDs cannot be preserved across managers, so each copy receives a fresh ID here. If
+    /// any allocation does not fit, this manager is restored exactly as it was, including
+    /// its free list, ID counter and statistics.
+    ///
+    /// Args:
+    ///     other (&MemoryManager): The manager whose allocations are copied.
+    ///
+    /// Returns:
+    ///     Result<Vec<(usize, usize)>, AllocError>: The (old ID, new ID) pairs in ascending old-ID order,
+    ///     or the error of the first allocation that failed.
+    /// """
+    pub fn import_from(&mut self, other: &MemoryManager) -> Result<Vec<(usize, usize)>, AllocError> {
+        let saved = self.clone();
+
+        let mut mapping = Vec::with_capacity(other.allocated_blocks.len());
+        for (&old_id, block) in &other.allocated_blocks {
+            let data = &other.memory[block.start..block.start + block.size];
+            match self.insert(block.size, data) {
+                Ok(new_id) => mapping.push((old_id, new_id)),
+                Err(err) => {
+                    *self = saved;
+                    return Err(err);
+                }
+            }
+        }
+        Ok(mapping)
+    }
+
+    /// """Lists every free and allocated region in ascending address order.
+    ///
+    /// Address-adjacent free blocks are reported as a single free region. Together the
+    /// regions cover the entire memory with no gaps or overlaps.
+    ///
+    /// Returns:
+    ///     Vec<Region>: The regions sorted by starting address.
+    /// """
+    pub fn regions(&self) -> Vec<Region> {
+        let mut regions: Vec<Region> = self
+            .merged_free_regions()
+            .into_iter()
+            .map(|(start, size)| Region {
+                start,
+                size,
+                kind: RegionKind::Free,
+            })
+            .chain(self.allocated_blocks.iter().map(|(&id, block)| Region {
+                start: block.start,
+                size: block.size,
+                kind: RegionKind::Allocated { id },
+            }))
+            .chain(self.reserved.iter().map(|&(start, size)| Region {
+                start,
+                size,
+                kind: RegionKind::Reserved,
+            }))
+            .collect();
+        regions.sort_unstable_by_key(|region| (region.start, region.size));
+        regions
+    }
+
+    /// """Resizes an allocation, keeping its ID.
+    ///
+    /// Shrinking always happens in place: the block keeps its starting address and its
+    /// first `new_size` bytes, and the tail is returned to the free list. Growing moves the
+    /// data to the best-fitting free block, zero-fills the new tail and frees the old region.
+    ///
+    /// Args:
+    ///     id (usize): The unique allocation ID of the block to resize.
+    ///     new_size (usize): The new size of the block in bytes.
+    ///
+    /// Returns:
+    ///     Result<(), AllocError>: Ok(()) on success, `AllocationTooLarge` if `new_size` exceeds the
+    ///     maximum allocation size, `UnknownId` if the ID is not allocated, or `OutOfMemory` if a
+    ///     growing block does not fit anywhere (the block is left unchanged).
+    /// """
+    pub fn realloc(&mut self, id: usize, new_size: usize) -> Result<(), AllocError> {
+        self.check_alloc_size(new_size)?;
+        let block = self.allocated_blocks.get(&id).ok_or(AllocError::UnknownId(id))?;
+        let (start, size) = (block.start, block.size);
+
+        if new_size <= size {
+            // Shrink in place, never moving data.
+            if new_size < size {
+                self.allocated_blocks.get_mut(&id).unwrap().size = new_size;
+                self.release_region(start + new_size, size - new_size);
+            }
+            return Ok(());
+        }
+
+        let (key, index) = self
+            .search_free_blocks(|manager| manager.find_best_fit(new_size))
+            .ok_or(AllocError::OutOfMemory { requested: new_size })?;
+        let target = self.take_free_block(key, index);
+
+        self.memory.copy_within(start..start + size, target.start);
+        self.memory[target.start + size..target.start + new_size].fill(0);
+        if target.size > new_size {
+            self.add_free_block(target.start + new_size, target.size - new_size);
+        }
+        self.release_region(start, size);
+
+        self.set_start(id, target.start);
+        self.allocated_blocks.get_mut(&id).unwrap().size = new_size;
+        Ok(())
+    }
+
+    /// """Finds allocations that sit between two free regions.
+    ///
+    /// Removing or relocating such an allocation would let the free regions on either side
+    /// merge into one, so these are the best targets for a relocation pass.
+    ///
+    /// Returns:
+    ///     Vec<usize>: The IDs of the blocking allocations in ascending address order.
+    /// """
+    pub fn blocking_allocations(&self) -> Vec<usize> {
+        self.regions()
+            .windows(3)
+            .filter_map(|window| match (window[0].kind, window[1].kind, window[2].kind) {
+                (RegionKind::Free, RegionKind::Allocated { id }, RegionKind::Free) => Some(id),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// """Relocates only the blocking allocations toward the low end of memory.
+    ///
+    /// Each allocation that sits between two free regions is moved into the lowest-addressed
+    /// free block that can hold it, provided that block lies below its current address. The
+    /// vacated regions are then merged with their free neighbours. Unlike `compact`, other
+    /// allocations never move. IDs stay the same.
+    ///
+    /// Returns:
+    ///     usize: The number of allocations moved.
+    /// """
+    pub fn smart_defrag(&mut self) -> usize {
+        self.coalesce();
+        let mut moved = 0;
+        for id in self.blocking_allocations() {
+            let block = &self.allocated_blocks[&id];
+            let (start, size) = (block.start, block.size);
+            if let Some((key, index)) = self.find_lowest_fit(size, start) {
+                self.move_into(id, key, index);
+                moved += 1;
+            }
+        }
+        self.coalesce();
+        moved
+    }
+
+    /// """Finds the lowest-addressed free block below `limit` that can hold `size` bytes.
+    ///
+    /// Args:
+    ///     size (usize): The number of bytes required.
+    ///     limit (usize): Only blocks starting before this address are considered.
+    ///
+    /// Returns:
+    ///     Option<(usize, usize)>: The size key and index within its bucket of the chosen block, if any.
+    /// """
+    fn find_lowest_fit(&self, size: usize, limit: usize) -> Option<(usize, usize)> {
+        self.free_blocks
+            .range(size..)
+            .flat_map(|(&key, blocks)| {
+                blocks
+                    .iter()
+                    .enumerate()
+                    .map(move |(index, block)| (block.start, key, index))
+            })
+            .filter(|&(start, _, _)| start < limit)
+            .min()
+            .map(|(_, key, index)| (key, index))
+    }
+
+    /// """Moves an allocation's data into the start of a free block and frees its old region.
+    ///
+    /// Args:
+    ///     id (usize): The unique allocation ID of the block to move.
+    ///     key (usize): The size key of the destination free block.
+    ///     index (usize): The position of the destination block within its bucket.
+    ///
+    /// Returns:
+    ///     None
+    /// """
+    fn move_into(&mut self, id: usize, key: usize, index: usize) {
+        let target = self.take_free_block(key, index);
+        let block = &self.allocated_blocks[&id];
+        let (start, size) = (block.start, block.size);
+        self.set_start(id, target.start);
+
+        self.memory.copy_within(start..start + size, target.start);
+        if target.size > size {
+            self.add_free_block(target.start + size, target.size - size);
+        }
+        self.release_region(start, size);
+    }
+
+    /// """Captures the allocation table without the stored bytes.
+    ///
+    /// This is a compact record of the layout, much smaller than the full memory contents.
+    ///
+    /// Returns:
+    ///     Vec<(usize, usize, usize)>: The (id, start, size) of every allocation in ascending ID order.
+    /// """
+    pub fn layout_snapshot(&self) -> Vec<(usize, usize, usize)> {
+        self.allocated_blocks
+            .iter()
+            .map(|(&id, block)| (id, block.start, block.size))
+            .collect()
+    }
+
+    /// """Serializes `layout_snapshot` as a JSON array of `[id, start, size]` triples.
+    ///
+    /// Only available with the `serde` feature.
+    ///
+    /// Returns:
+    ///     String: The JSON encoding of the allocation table.
+    /// """
+    #[cfg(feature = "serde")]
+    pub fn layout_snapshot_json(&self) -> String {
+        serde_json::to_string(&self.layout_snapshot()).expect("a list of integers always serializes")
+    }
+
+    /// """Reports whether an address lies within free memory.
+    ///
+    /// Args:
+    ///     address (usize): The address to check.
+    ///
+    /// Returns:
+    ///     bool: True if some free block contains `address`, False if it is allocated or out of range.
+    /// """
+    pub fn is_free(&self, address: usize) -> bool {
+        let intervals = self.free_intervals();
+        let index = intervals.partition_point(|&(_, end)| end <= address);
+        intervals.get(index).is_some_and(|&(start, _)| start <= address)
+    }
+
+    /// """Inserts data, splitting it across several free blocks if no single block fits.
+    ///
+    /// A contiguous allocation is tried first. If it fails but enough free space exists in
+    /// total, the data is split into pieces placed in the largest free blocks, each piece
+    /// becoming its own allocation. The bytes are then no longer contiguous in memory; use
+    /// `read_scattered` with the returned IDs to reassemble them.
+    ///
+    /// Args:
+    ///     size (usize): The total number of bytes to allocate.
+    ///     data (&[u8]): A byte slice containing the data to be stored.
+    ///
+    /// Returns:
+    ///     Result<Vec<usize>, AllocError>: The allocation IDs holding the data, in data order,
+    ///     or `OutOfMemory` if the total free space is insufficient (nothing is allocated).
+    /// """
+    pub fn insert_scattered(&mut self, size: usize, data: &[u8]) -> Result<Vec<usize>, AllocError> {
+        self.check_alloc_size(size)?;
+        if let Ok(id) = self.insert(size, data) {
+            return Ok(vec![id]);
+        }
+        if self.stats().free_bytes < size {
+            return Err(AllocError::OutOfMemory { requested: size });
+        }
+
+        let mut ids = Vec::new();
+        let mut offset = 0;
+        while offset < size {
+            // Take from the largest free block to keep the number of pieces small.
+            let key = *self.free_blocks.keys().next_back().unwrap();
+            let block = self.take_free_block(key, 0);
+            let piece = block.size.min(size - offset);
+
+            ids.push(self.allocate_at(block.start, piece, data.get(offset..).unwrap_or(&[])));
+            if block.size > piece {
+                self.add_free_block(block.start + piece, block.size - piece);
+            }
+            offset += piece;
+        }
+        Ok(ids)
+    }
+
+    /// """Reassembles data stored across several allocations, such as by `insert_scattered`.
+    ///
+    /// Args:
+    ///     ids (&[usize]): The allocation IDs holding the pieces, in data order.
+    ///
+    /// Returns:
+    ///     Option<Vec<u8>>: The concatenated bytes, or None if any ID is not allocated.
+    /// """
+    pub fn read_scattered(&self, ids: &[usize]) -> Option<Vec<u8>> {
+        let mut data = Vec::new();
+        for &id in ids {
+            data.extend_from_slice(self.find(id)?);
+        }
+        Some(data)
+    }
+
+    /// """Groups allocations whose stored bytes are identical.
+    ///
+    /// Blocks are bucketed by content in a hash map, so the cost is linear in the number of
+    /// allocated bytes rather than quadratic in the number of allocations. Summing
+    /// `size * (len - 1)` over the groups gives the space interning could save.
+    ///
+    /// Returns:
+    ///     Vec<Vec<usize>>: Each group of two or more IDs with identical contents, IDs ascending
+    ///     within a group and groups ordered by their lowest ID.
+    /// """
+    pub fn duplicate_groups(&self) -> Vec<Vec<usize>> {
+        let mut group_of: HashMap<&[u8], usize> = HashMap::new();
+        let mut groups: Vec<Vec<usize>> = Vec::new();
+        for (&id, block) in &self.allocated_blocks {
+            let contents = &self.memory[block.start..block.start + block.size];
+            let group = *group_of.entry(contents).or_insert_with(|| {
+                groups.push(Vec::new());
+                groups.len() - 1
+            });
+            groups[group].push(id);
+        }
+        groups.retain(|group| group.len() > 1);
+        groups
+    }
+
+    /// """Frees every allocation that duplicates the contents of a lower-ID allocation.
+    ///
+    /// Within each group reported by `duplicate_groups`, the lowest ID survives and the
+    /// others are freed. Callers holding freed IDs should switch to the survivor given in
+    /// the returned remap.
+    ///
+    /// Returns:
+    ///     (BTreeMap<usize, usize>, usize): The map from each freed ID to its surviving ID, and
+    ///     the number of bytes reclaimed.
+    /// """
+    pub fn dedup_with_remap(&mut self) -> (BTreeMap<usize, usize>, usize) {
+        let mut remap = BTreeMap::new();
+        let mut reclaimed = 0;
+        for group in self.duplicate_groups() {
+            let survivor = group[0];
+            for id in group.into_iter().skip(1) {
+                let block = self.allocated_blocks.remove(&id).unwrap();
+                self.by_address.remove(&(block.start, id));
+                self.release_region(block.start, block.size);
+                reclaimed += block.size;
+                remap.insert(id, survivor);
+            }
+        }
+        (remap, reclaimed)
+    }
+
+    /// """Frees duplicate-content allocations, keeping the lowest ID of each group.
+    ///
+    /// Use `dedup_with_remap` to learn which surviving ID replaces each freed one.
+    ///
+    /// Returns:
+    ///     usize: The number of bytes reclaimed.
+    /// """
+    pub fn dedup(&mut self) -> usize {
+        self.dedup_with_remap().1
+    }
+
+    /// """Measures the contiguous free space on either side of an address.
+    ///
+    /// The space below is the free run ending at `address`, and the space above is the free
+    /// run starting at it. If `address` lies inside a free interval, that interval is split
+    /// at `address`. An allocation at `address` could therefore grow downward or upward by
+    /// at most these amounts.
+    ///
+    /// Args:
+    ///     address (usize): The address to measure around.
+    ///
+    /// Returns:
+    ///     (usize, usize): The free bytes immediately below and immediately above `address`.
+    /// """
+    pub fn free_around(&self, address: usize) -> (usize, usize) {
+        let mut below = 0;
+        let mut above = 0;
+        for (start, end) in self.free_intervals() {
+            if start < address && address <= end {
+                below = address - start;
+            }
+            if start <= address && address < end {
+                above = end - address;
+            }
+        }
+        (below, above)
+    }
+
+    /// """Finds the allocations that overlap an address range.
+    ///
+    /// Allocations are indexed by address, so only the one allocation that can start before
+    /// `start` and the allocations starting inside the range are examined. Zero-size
+    /// allocations occupy no bytes and never overlap.
+    ///
+    /// Args:
+    ///     start (usize): The first address of the range.
+    ///     end (usize): The address one past the end of the range.
+    ///
+    /// Returns:
+    ///     Vec<usize>: The IDs of allocations sharing at least one byte with `[start, end)`,
+    ///     in ascending address order.
+    /// """
+    pub fn allocations_in_range(&self, start: usize, end: usize) -> Vec<usize> {
+        if start >= end {
+            return Vec::new();
+        }
+        // Allocations do not overlap, so at most the last non-empty one starting before
+        // `start` can reach into the range.
+        let before = self
+            .by_address
+            .range(..(start, 0))
+            .rev()
+            .find(|(_, id)| self.allocated_blocks[id].size > 0)
+            .filter(|&&(block_start, id)| block_start + self.allocated_blocks[&id].size > start);
+        before
+            .into_iter()
+            .chain(self.by_address.range((start, 0)..(end, 0)))
+            .map(|&(_, id)| id)
+            .filter(|id| self.allocated_blocks[id].size > 0)
+            .collect()
+    }
+
+    /// """Returns the first `N` bytes of an allocation as a fixed-size array.
+    ///
+    /// Args:
+    ///     id (usize): The unique allocation ID of the block to read.
+    ///
+    /// Returns:
+    ///     Option<[u8; N]>: The leading `N` bytes, or None if the ID is not allocated or the
+    ///     block is shorter than `N` bytes.
+    /// """
+    pub fn read_array<const N: usize>(&self, id: usize) -> Option<[u8; N]> {
+        self.find(id)?.get(..N)?.try_into().ok()
+    }
+
+    /// """Allocates a table of equal-size records from one contiguous region.
+    ///
+    /// A single free block of `record_size * count` bytes is chosen as `insert` would choose
+    /// it and then split into `count` allocations, which is much cheaper than `count`
+    /// separate searches. Record `i` receives `data[i * record_size..]`, truncated to the
+    /// record and zero-filled past the end of `data`.
+    ///
+    /// Args:
+    ///     record_size (usize): The size of each record in bytes.
+    ///     count (usize): The number of records.
+    ///     data (&[u8]): The bytes to write across the records, in order.
+    ///
+    /// Returns:
+    ///     Result<Vec<usize>, AllocError>: The record IDs in address order, `AllocationTooLarge` if
+    ///     `record_size` exceeds the configured maximum, or `OutOfMemory` if no free block can
+    ///     hold the whole table (nothing is allocated).
+    /// """
+    pub fn insert_table(&mut self, record_size: usize, count: usize, data: &[u8]) -> Result<Vec<usize>, AllocError> {
+        self.check_alloc_size(record_size)?;
+        if count == 0 {
+            return Ok(Vec::new());
+        }
+        let total = record_size.saturating_mul(count);
+        let block = self
+            .take_fitting_block(total)
+            .ok_or(AllocError::OutOfMemory { requested: total })?;
+
+        let ids = (0..count)
+            .map(|i| {
+                let offset = i * record_size;
+                self.allocate_at(block.start + offset, record_size, data.get(offset..).unwrap_or(&[]))
+            })
+            .collect();
+        if block.size > total {
+            self.add_free_block(block.start + total, block.size - total);
+        }
+        Ok(ids)
+    }
+
+    /// """Finds the longest span of back-to-back allocations with no free gap between them.
+    ///
+    /// Such dense spans are the parts of memory that compaction would leave in place.
+    ///
+    /// Returns:
+    ///     (usize, usize): The (start, size) of the longest allocated run, the lowest-addressed
+    ///     one on ties, or (0, 0) if nothing is allocated.
+    /// """
+    pub fn longest_allocated_run(&self) -> (usize, usize) {
+        let mut longest = (0, 0);
+        let mut current: Option<(usize, usize)> = None;
+        for region in self.regions() {
+            match region.kind {
+                RegionKind::Allocated { .. } => {
+                    let (start, size) = current.get_or_insert((region.start, 0));
+                    *size += region.size;
+                    if *size > longest.1 {
+                        longest = (*start, *size);
+                    }
+                }
+                RegionKind::Free | RegionKind::Reserved => current = None,
+            }
+        }
+        longest
+    }
+
+    /// """Checks whether an allocation holds exactly the expected bytes.
+    ///
+    /// Args:
+    ///     id (usize): The unique allocation ID of the block to check.
+    ///     expected (&[u8]): The bytes the whole block should contain.
+    ///
+    /// Returns:
+    ///     bool: True if the ID is allocated and its bytes equal `expected`, False otherwise.
+    /// """
+    pub fn verify(&self, id: usize, expected: &[u8]) -> bool {
+        self.find(id) == Some(expected)
+    }
+
+    /// """Measures the distance from one allocation's start to another's.
+    ///
+    /// Offsets are only valid until either allocation moves, e.g. through compaction.
+    ///
+    /// Args:
+    ///     a (usize): The ID of the allocation to measure from.
+    ///     b (usize): The ID of the allocation to measure to.
+    ///
+    /// Returns:
+    ///     Option<isize>: `b`'s start minus `a`'s start, or None if either ID is not allocated.
+    /// """
+    pub fn offset_between(&self, a: usize, b: usize) -> Option<isize> {
+        let from = self.allocated_blocks.get(&a)?.start;
+        let to = self.allocated_blocks.get(&b)?.start;
+        Some(to as isize - from as isize)
+    }
+
+    /// """Consumes the manager and returns a read-only view of it.
+    ///
+    /// Use this once a data set is fully loaded to rule out accidental modification.
+    ///
+    /// Returns:
+    ///     FrozenManager: A view exposing only read operations.
+    /// """
+    pub fn freeze(self) -> FrozenManager {
+        FrozenManager::new(self)
+    }
+
+    /// """Reserves a zero-filled block whose starting address is a multiple of `align`.
+    ///
+    /// The smallest free block that can hold `size` bytes at an aligned address is used.
+    /// Any space before the aligned start and after the allocation is returned to the free list.
+    ///
+    /// Args:
+    ///     size (usize): The number of bytes to allocate.
+    ///     align (usize): The required alignment in bytes; must be a power of two.
+    ///
+    /// Returns:
+    ///     Result<usize, AllocError>: A unique allocation ID, `InvalidAlignment` if `align` is not a
+    ///     power of two, `AllocationTooLarge` if `size` exceeds the configured maximum, or
+    ///     `OutOfMemory` if no free block can hold an aligned allocation.
+    /// """
+    pub fn reserve_aligned(&mut self, size: usize, align: usize) -> Result<usize, AllocError> {
+        if !align.is_power_of_two() {
+            return Err(AllocError::InvalidAlignment(align));
+        }
+        self.check_alloc_size(size)?;
+
+        let aligned_fit = |block: &MemoryBlock| {
+            let start = block.start.checked_next_multiple_of(align)?;
+            (start + size <= block.start + block.size).then_some(start)
+        };
+        let (key, index) = self
+            .search_free_blocks(|manager| {
+                manager.free_blocks.range(size..).find_map(|(&key, blocks)| {
+                    blocks
+                        .iter()
+                        .position(|block| aligned_fit(block).is_some())
+                        .map(|index| (key, index))
+                })
+            })
+            .ok_or(AllocError::OutOfMemory { requested: size })?;
+        let block = self.take_free_block(key, index);
+        let start = aligned_fit(&block).unwrap();
+        Ok(self.allocate_within(block, start, size, &[]))
+    }
+
+    /// """Allocates `size` bytes at `start` inside a block already taken from the free list.
+    ///
+    /// Any space before `start` and after the allocation is returned to the free list.
+    ///
+    /// Args:
+    ///     block (MemoryBlock): The free block that contains the allocation.
+    ///     start (usize): The allocation's starting address within `block`.
+    ///     size (usize): The number of bytes to allocate.
+    ///     data (&[u8]): The bytes to store, truncated or zero-filled to `size`.
+    ///
+    /// Returns:
+    ///     usize: The new allocation ID.
+    /// """
+    fn allocate_within(&mut self, block: MemoryBlock, start: usize, size: usize, data: &[u8]) -> usize {
+        let new_id = self.allocate_at(start, size, data);
+        if start > block.start {
+            self.add_free_block(block.start, start - block.start);
+        }
+        let (end, block_end) = (start + size, block.start + block.size);
+        if block_end > end {
+            self.add_free_block(end, block_end - end);
+        }
+        new_id
+    }
+
+    /// """Inserts data like `insert` and labels the allocation with a numeric type tag.
+    ///
+    /// Args:
+    ///     size (usize): The number of bytes to allocate.
+    ///     data (&[u8]): A byte slice containing the data to be stored.
+    ///     tag (u16): The caller-defined category of the allocation, e.g. 0 for headers and 1 for payloads.
+    ///
+    /// Returns:
+    ///     Result<usize, AllocError>: A unique allocation ID, or the error `insert` would return.
+    /// """
+    pub fn insert_tagged(&mut self, size: usize, data: &[u8], tag: u16) -> Result<usize, AllocError> {
+        let id = self.insert(size, data)?;
+        self.allocated_blocks.get_mut(&id).unwrap().tag = tag;
+        Ok(id)
+    }
+
+    /// """Lists the allocations carrying a type tag.
+    ///
+    /// Allocations made without `insert_tagged` have tag 0.
+    ///
+    /// Args:
+    ///     tag (u16): The tag to look for.
+    ///
+    /// Returns:
+    ///     Vec<usize>: The IDs of matching allocations in ascending order.
+    /// """
+    pub fn ids_with_tag(&self, tag: u16) -> Vec<usize> {
+        self.allocated_blocks
+            .iter()
+            .filter(|(_, block)| block.tag == tag)
+            .map(|(&id, _)| id)
+            .collect()
+    }
+
+    /// """Iterates over the records of a table created by `insert_table`.
+    ///
+    /// Each item is the first `record_size` bytes of the next ID's block. IDs that are not
+    /// allocated are skipped, and blocks shorter than `record_size` yield their whole contents.
+    ///
+    /// Args:
+    ///     ids (&[usize]): The record IDs, in the order to read them.
+    ///     record_size (usize): The size of each record in bytes.
+    ///
+    /// Returns:
+    ///     impl Iterator<Item = &[u8]>: The record byte slices in order.
+    /// """
+    pub fn records<'a>(&'a self, ids: &'a [usize], record_size: usize) -> impl Iterator<Item = &'a [u8]> {
+        ids.iter()
+            .filter_map(move |&id| self.find(id))
+            .map(move |data| &data[..record_size.min(data.len())])
+    }
+
+    /// """Reports whether a request would succeed only after compacting.
+    ///
+    /// Args:
+    ///     for_size (usize): The size of the planned allocation in bytes.
+    ///
+    /// Returns:
+    ///     bool: True if `for_size` exceeds the largest contiguous free region but fits in the
+    ///     largest region `compact` would leave, False if it already fits or cannot fit even after
+    ///     compaction (e.g. because reserved ranges split the free space).
+    /// """
+    pub fn needs_compaction(&self, for_size: usize) -> bool {
+        let after_compaction = self.compacted_gaps().iter().map(|&(_, size)| size).max().unwrap_or(0);
+        for_size > self.stats().largest_gap && for_size <= after_compaction
+    }
+
+    /// """Applies a batch of updates, reporting the outcome of each one.
+    ///
+    /// Every item is validated and written exactly as `update` would, so a failing item does
+    /// not stop later items from being applied.
+    ///
+    /// Args:
+    ///     items (IntoIterator<Item = (usize, Vec<u8>)>): The (id, new data) pairs, applied in order.
+    ///
+    /// Returns:
+    ///     Vec<Result<(), AllocError>>: The result of each update, in the order given.
+    /// """
+    pub fn update_many<I: IntoIterator<Item = (usize, Vec<u8>)>>(&mut self, items: I) -> Vec<Result<(), AllocError>> {
+        items
+            .into_iter()
+            .map(|(id, data)| self.update(id, &data))
+            .collect()
+    }
+
+    /// """Captures how the manager is configured.
+    ///
+    /// Returns:
+    ///     ManagerConfig: The current settings, whether they came from the builder or setters.
+    /// """
+    pub fn config(&self) -> ManagerConfig {
+        ManagerConfig {
+            capacity: self.capacity(),
+            fill_pattern: self.fill_pattern,
+            zero_on_free: self.zero_on_free,
+            strategy: self.strategy,
+            custom_selector: self.selector.is_some(),
+            coalesce_mode: self.coalesce_mode,
+            max_alloc_size: self.max_alloc_size,
+            auto_compact_every: self.auto_compact_every,
+            auto_compact_threshold: self.auto_compact_threshold,
+            max_total_operations: self.max_total_operations,
+            strict: self.strict,
+            unknown_policy: self.unknown_policy,
+            radix: self.radix,
+            log_format: self.log_format,
+        }
+    }
+
+    /// """Moves an allocation to an exact starting address, keeping its ID.
+    ///
+    /// The destination range must lie entirely within free memory, so it can never overlap
+    /// the block's current location. The data is copied and the old region is freed.
+    ///
+    /// Args:
+    ///     id (usize): The unique allocation ID of the block to move.
+    ///     new_start (usize): The address the block should start at.
+    ///
+    /// Returns:
+    ///     Result<(), AllocError>: Ok(()) if the block was moved, `UnknownId` if the ID is not
+    ///     allocated, or `RegionNotFree` if the destination range is not entirely free or
+    ///     extends past the end of memory.
+    /// """
+    pub fn move_to(&mut self, id: usize, new_start: usize) -> Result<(), AllocError> {
+        let size = self.allocated_blocks.get(&id).ok_or(AllocError::UnknownId(id))?.size;
+        self.take_free_range(new_start, size)?;
+
+        let old_start = self.allocated_blocks[&id].start;
+        self.set_start(id, new_start);
+        self.memory.copy_within(old_start..old_start + size, new_start);
+        self.release_region(old_start, size);
+        Ok(())
+    }
+
+    /// """Removes an exact address range from the free list, returning any remainder.
+    ///
+    /// The range must lie within contiguous free memory; free neighbours that were never merged
+    /// (in lazy mode, or after `free_list_import`) are merged first if needed. An empty range
+    /// is available anywhere within memory.
+    ///
+    /// Args:
+    ///     start (usize): The first address of the range.
+    ///     size (usize): The length of the range in bytes.
+    ///
+    /// Returns:
+    ///     Result<(), AllocError>: Ok(()) if the range is no longer on the free list, or
+    ///     `RegionNotFree` if it was not entirely free or extends past the end of memory (no free
+    ///     memory is then taken).
+    /// """
+    fn take_free_range(&mut self, start: usize, size: usize) -> Result<(), AllocError> {
+        let end = match start.checked_add(size) {
+            Some(end) if end <= self.capacity() => end,
+            _ => return Err(AllocError::RegionNotFree { start, size }),
+        };
+        if size == 0 {
+            return Ok(());
+        }
+        let containing = |manager: &Self| {
+            manager.free_blocks.iter().find_map(|(&key, blocks)| {
+                blocks
+                    .iter()
+                    .position(|block| block.start <= start && end <= block.start + block.size)
+                    .map(|index| (key, index))
+            })
+        };
+        let mut found = containing(self);
+        // The range may span free blocks that have not been merged yet.
+        if found.is_none() && self.coalesce() > 0 {
+            found = containing(self);
+        }
+        let (key, index) = found.ok_or(AllocError::RegionNotFree { start, size })?;
+        let target = self.take_free_block(key, index);
+        if start > target.start {
+            self.add_free_block(target.start, start - target.start);
+        }
+        if target.start + target.size > end {
+            self.add_free_block(end, target.start + target.size - end);
+        }
+        Ok(())
+    }
+
+    /// """Lists the allocations whose block is exactly a given size.
+    ///
+    /// Args:
+    ///     size (usize): The block size to look for, in bytes.
+    ///
+    /// Returns:
+    ///     Vec<usize>: The IDs of matching allocations in ascending order.
+    /// """
+    pub fn ids_with_size(&self, size: usize) -> Vec<usize> {
+        self.allocated_blocks
+            .iter()
+            .filter(|(_, block)| block.size == size)
+            .map(|(&id, _)| id)
+            .collect()
+    }
+
+    /// """Records a hash of every allocation's contents for a later `diff`.
+    ///
+    /// Returns:
+    ///     Checkpoint: The recorded state.
+    /// """
+    pub fn checkpoint(&self) -> Checkpoint {
+        let hashes = self
+            .allocated_blocks
+            .keys()
+            .map(|&id| (id, self.content_hash(id)))
+            .collect();
+        Checkpoint { hashes }
+    }
+
+    /// """Lists the allocations that changed since a checkpoint.
+    ///
+    /// Moving a block without changing its bytes, e.g. by compaction, is not a change.
+    ///
+    /// Args:
+    ///     cp (&Checkpoint): A checkpoint previously taken from this manager.
+    ///
+    /// Returns:
+    ///     Vec<Change>: The added, removed and modified IDs, in ascending ID order.
+    /// """
+    pub fn diff(&self, cp: &Checkpoint) -> Vec<Change> {
+        let mut changes: Vec<Change> = cp
+            .hashes
+            .iter()
+            .filter_map(|(&id, &hash)| {
+                if !self.allocated_blocks.contains_key(&id) {
+                    Some(Change::Removed(id))
+                } else if self.content_hash(id) != hash {
+                    Some(Change::Modified(id))
+                } else {
+                    None
+                }
+            })
+            .chain(
+                self.allocated_blocks
+                    .keys()
+                    .filter(|id| !cp.hashes.contains_key(id))
+                    .map(|&id| Change::Added(id)),
+            )
+            .collect();
+        changes.sort_unstable_by_key(|change| match *change {
+            Change::Added(id) | Change::Removed(id) | Change::Modified(id) => id,
+        });
+        changes
+    }
+
+    /// """Hashes the bytes of an allocated block.
+    ///
+    /// Args:
+    ///     id (usize): The unique allocation ID of the block; it must be allocated.
+    ///
+    /// Returns:
+    ///     u64: The hash of the block's contents.
+    /// """
+    fn content_hash(&self, id: usize) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.find(id).unwrap().hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// """Reserves zero-filled blocks at the very bottom and very top of memory.
+    ///
+    /// This sets up a double-ended layout: fixed headers at both ends with the free space
+    /// between them. Both ranges must be free and within the maximum allocation size; if either
+    /// is not, nothing is reserved.
+    ///
+    /// Args:
+    ///     bottom (usize): The size of the block to reserve at address 0.
+    ///     top (usize): The size of the block to reserve ending at the capacity.
+    ///
+    /// Returns:
+    ///     Result<(usize, usize, usize), AllocError>: The bottom ID, the top ID and the size of the
+    ///     free block left between them, `AllocationTooLarge` if either size exceeds the maximum
+    ///     allocation size, or `RegionNotFree` if either end is not free or the two reservations
+    ///     would overlap.
+    /// """
+    pub fn reserve_ends(&mut self, bottom: usize, top: usize) -> Result<(usize, usize, usize), AllocError> {
+        self.check_alloc_size(bottom)?;
+        self.check_alloc_size(top)?;
+        let capacity = self.capacity();
+        if bottom.saturating_add(top) > capacity {
+            return Err(AllocError::RegionNotFree { start: 0, size: bottom.saturating_add(top) });
+        }
+        let top_start = capacity - top;
+        for (start, size) in [(0, bottom), (top_start, top)] {
+            let free = self
+                .free_intervals()
+                .into_iter()
+                .any(|(free_start, free_end)| free_start <= start && start + size <= free_end);
+            if size > 0 && !free {
+                return Err(AllocError::RegionNotFree { start, size });
+            }
+        }
+
+        // Both ranges lie in contiguous free memory and `take_free_range` merges split free
+        // blocks as needed, so neither take can fail once the check above has passed.
+        self.take_free_range(0, bottom)?;
+        let bottom_id = self.allocate_at(0, bottom, &[]);
+        self.take_free_range(top_start, top)?;
+        let top_id = self.allocate_at(top_start, top, &[]);
+        let middle = self.free_around(bottom).1;
+        Ok((bottom_id, top_id, middle))
+    }
+
+    /// """Lists the live allocations created within a range of allocation ticks.
+    ///
+    /// Every allocation is stamped with the next tick of a counter that starts at 0 and is
+    /// only restarted by `reset`, so tick ranges split a long run into phases.
+    ///
+    /// Args:
+    ///     from_tick (usize): The first tick of the range.
+    ///     to_tick (usize): The tick one past the end of the range.
+    ///
+    /// Returns:
+    ///     Vec<usize>: The IDs of allocations created in `[from_tick, to_tick)`, in ascending ID order.
+    /// """
+    pub fn allocations_between(&self, from_tick: usize, to_tick: usize) -> Vec<usize> {
+        self.allocated_blocks
+            .iter()
+            .filter(|(_, block)| (from_tick..to_tick).contains(&block.created))
+            .map(|(&id, _)| id)
+            .collect()
+    }
+
+    /// """Computes the mean size of the live allocations.
+    ///
+    /// Returns:
+    ///     f64: The used bytes divided by the number of allocations, or 0.0 when nothing is allocated.
+    /// """
+    pub fn average_allocation_size(&self) -> f64 {
+        self.stats().average_allocation_size()
+    }
+
+    /// """Reads a whole file and stores its bytes in a block of exactly the file's size.
+    ///
+    /// Args:
+    ///     path (&Path): The file to load.
+    ///
+    /// Returns:
+    ///     io::Result<Result<usize, AllocError>>: An I/O error if the file cannot be read; otherwise
+    ///     the result of inserting its contents, as for `insert_data`.
+    /// """
+    pub fn insert_file(&mut self, path: &Path) -> io::Result<Result<usize, AllocError>> {
+        let data = fs::read(path)?;
+        Ok(self.insert_data(&data))
+    }
+
+    /// """Writes the data of an allocated block to a file, replacing any existing contents.
+    ///
+    /// Args:
+    ///     id (usize): The unique allocation ID whose bytes are written.
+    ///     path (&Path): The file to create or truncate.
+    ///
+    /// Returns:
+    ///     io::Result<()>: Ok(()) on success, a `NotFound` error wrapping `AllocError::UnknownId`
+    ///     if the ID is not allocated, or the error from writing the file.
+    /// """
+    pub fn write_file(&self, id: usize, path: &Path) -> io::Result<()> {
+        let data = self.find(id).ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, AllocError::UnknownId(id)))?;
+        fs::write(path, data)
+    }
+
+    /// """Stores data at the lowest address that is a multiple of `align` and has `size` free bytes.
+    ///
+    /// Unlike `reserve_aligned`, which prefers the smallest suitable free block, every free block
+    /// is considered and the one yielding the lowest aligned start wins, so the same trace always
+    /// produces the same address-ordered layout.
+    ///
+    /// Args:
+    ///     size (usize): The number of bytes to allocate.
+    ///     align (usize): The required alignment in bytes; must be a power of two.
+    ///     data (&[u8]): The bytes to store, truncated or zero-filled to `size`.
+    ///
+    /// Returns:
+    ///     Result<usize, AllocError>: A unique allocation ID, `InvalidAlignment` if `align` is not a
+    ///     power of two, `AllocationTooLarge` if `size` exceeds the configured maximum, or
+    ///     `OutOfMemory` if no free block can hold an aligned allocation.
+    /// """
+    pub fn first_fit_aligned(&mut self, size: usize, align: usize, data: &[u8]) -> Result<usize, AllocError> {
+        if !align.is_power_of_two() {
+            return Err(AllocError::InvalidAlignment(align));
+        }
+        self.check_alloc_size(size)?;
+
+        let aligned_fit = |block: &MemoryBlock| {
+            let start = block.start.checked_next_multiple_of(align)?;
+            (start + size <= block.start + block.size).then_some(start)
+        };
+        let (key, index, start) = self
+            .search_free_blocks(|manager| {
+                manager
+                    .free_blocks
+                    .range(size..)
+                    .flat_map(|(&key, blocks)| {
+                        blocks
+                            .iter()
+                            .enumerate()
+                            .filter_map(move |(index, block)| aligned_fit(block).map(|start| (key, index, start)))
+                    })
+                    .min_by_key(|&(_, _, start)| start)
+            })
+            .ok_or(AllocError::OutOfMemory { requested: size })?;
+        let block = self.take_free_block(key, index);
+        Ok(self.allocate_within(block, start, size, data))
+    }
+
+    /// """Totals the free bytes held in blocks of each size.
+    ///
+    /// Many bytes under small sizes mean free space is stuck in slivers, while bytes
+    /// concentrated under a few large sizes mean it is still usable for big requests.
+    ///
+    /// Returns:
+    ///     BTreeMap<usize, usize>: Maps each free-block size to `size * count` for the free blocks of that size.
+    /// """
+    pub fn free_bytes_by_size(&self) -> BTreeMap<usize, usize> {
+        self.free_blocks
+            .iter()
+            .filter(|(_, blocks)| !blocks.is_empty())
+            .map(|(&size, blocks)| (size, size * blocks.len()))
+            .collect()
+    }
+
+    /// """Stores data in the free block whose start is closest to a preferred address.
+    ///
+    /// Among the free blocks that can hold `size` bytes, the one starting nearest `hint` is
+    /// used, with ties going to the lower address, and the data is placed at its start. This
+    /// clusters related allocations without requiring an exact address.
+    ///
+    /// Args:
+    ///     size (usize): The number of bytes to allocate.
+    ///     hint (usize): The preferred starting address.
+    ///     data (&[u8]): The bytes to store, truncated or zero-filled to `size`.
+    ///
+    /// Returns:
+    ///     Result<usize, AllocError>: A unique allocation ID, `AllocationTooLarge` if `size` exceeds
+    ///     the configured maximum, or `OutOfMemory` if no free block is large enough.
+    /// """
+    pub fn insert_near(&mut self, size: usize, hint: usize, data: &[u8]) -> Result<usize, AllocError> {
+        self.check_alloc_size(size)?;
+        let (key, index) = self
+            .search_free_blocks(|manager| {
+                manager
+                    .free_blocks
+                    .range(size..)
+                    .flat_map(|(&key, blocks)| blocks.iter().enumerate().map(move |(index, block)| (key, index, block.start)))
+                    .min_by_key(|&(_, _, start)| (start.abs_diff(hint), start))
+                    .map(|(key, index, _)| (key, index))
+            })
+            .ok_or(AllocError::OutOfMemory { requested: size })?;
+        let block = self.take_free_block(key, index);
+        let start = block.start;
+        Ok(self.allocate_within(block, start, size, data))
+    }
+
+    /// """Checks whether the manager is in the state `new` or `reset` leaves it in.
+    ///
+    /// The arena is pristine when nothing is allocated and the free list holds exactly one
+    /// block spanning the whole capacity, so a trace that frees everything but leaves the
+    /// free list unmerged (e.g. in lazy coalescing mode) is not pristine until it is coalesced.
+    /// Reserved ranges are kept by `reset`, so with them the free list must instead hold one
+    /// block for each region between reserved ranges. An arena of zero capacity is pristine
+    /// when it has no blocks at all.
+    ///
+    /// Returns:
+    ///     bool: True if there are no allocations and all unreserved memory is one free block per region.
+    /// """
+    pub fn is_pristine(&self) -> bool {
+        self.allocated_blocks.is_empty()
+            && self.free_blocks_ordered() == uncovered_gaps(self.reserved.clone(), self.capacity())
+    }
+
+    /// """Estimates how many free bytes a minimum split size would have kept out of slivers.
+    ///
+    /// This is a what-if over the current free list: a block is only worth splitting off if
+    /// the remainder is at least `min_split` bytes, so the free gaps smaller than that are the
+    /// bytes such a setting targets. Gaps are measured as by `slivers`, with address-adjacent
+    /// free blocks counted together.
+    ///
+    /// Args:
+    ///     min_split (usize): The candidate minimum remainder size in bytes.
+    ///
+    /// Returns:
+    ///     usize: The total size of the free gaps strictly smaller than `min_split`.
+    /// """
+    pub fn estimate_sliver_bytes(&self, min_split: usize) -> usize {
+        self.slivers(min_split).iter().map(|&(_, size)| size).sum()
+    }
+
+    /// """Lists the largest contiguous free gaps.
+    ///
+    /// Gaps come from the same merged view as `free_intervals`, so address-adjacent free
+    /// blocks count as one gap. Gaps of equal size are ordered by address.
+    ///
+    /// Args:
+    ///     n (usize): The maximum number of gaps to return.
+    ///
+    /// Returns:
+    ///     Vec<(usize, usize)>: The (start, size) of up to `n` gaps, largest first.
+    /// """
+    pub fn top_free_gaps(&self, n: usize) -> Vec<(usize, usize)> {
+        let mut gaps = self.merged_free_regions();
+        gaps.sort_by_key(|&(start, size)| (std::cmp::Reverse(size), start));
+        gaps.truncate(n);
+        gaps
+    }
+
+    /// """Measures how much of each of `buckets` equal address windows is allocated.
+    ///
+    /// Window `i` covers addresses `i * capacity / buckets` up to `(i + 1) * capacity / buckets`,
+    /// so windows differ by at most one byte when the capacity does not divide evenly. This
+    /// gives a memory map at a chosen resolution, e.g. for a heatmap of a large arena.
+    ///
+    /// Args:
+    ///     buckets (usize): The number of windows to divide memory into.
+    ///
+    /// Returns:
+    ///     Vec<f64>: The allocated fraction (0.0 to 1.0) of each window in address order; an
+    ///     empty window (more buckets than bytes) reports 0.0.
+    /// """
+    pub fn density(&self, buckets: usize) -> Vec<f64> {
+        let capacity = self.capacity();
+        let mut used: Vec<(usize, usize)> = self
+            .allocated_blocks
+            .values()
+            .map(|block| (block.start, block.start + block.size))
+            .collect();
+        used.sort_unstable();
+
+        let mut first = 0;
+        (0..buckets)
+            .map(|i| {
+                let (low, high) = (i * capacity / buckets, (i + 1) * capacity / buckets);
+                if high == low {
+                    return 0.0;
+                }
+                // Windows advance monotonically, so blocks ending before this one never matter again.
+                while first < used.len() && used[first].1 <= low {
+                    first += 1;
+                }
+                let allocated: usize = used[first..]
+                    .iter()
+                    .take_while(|&&(start, _)| start < high)
+                    .map(|&(start, end)| end.min(high) - start.max(low))
+                    .sum();
+                allocated as f64 / (high - low) as f64
+            })
+            .collect()
+    }
+
+    /// """Reassigns allocation IDs densely from 0 in ascending order of the current IDs.
+    ///
+    /// Data, addresses and creation ticks are untouched; only the IDs change, and `next_id`
+    /// continues from the number of live allocations. Each reassigned ID gets a new generation,
+    /// so handles taken before renumbering become stale.
+    ///
+    /// Returns:
+    ///     Vec<(usize, usize)>: The (old_id, new_id) of every allocation, in ascending order, so
+    ///     callers can update references they hold.
+    /// """
+    pub fn renumber(&mut self) -> Vec<(usize, usize)> {
+        let blocks = std::mem::take(&mut self.allocated_blocks);
+        let mut mapping = Vec::with_capacity(blocks.len());
+        for (new_id, (old_id, mut block)) in blocks.into_iter().enumerate() {
+            if new_id != old_id {
+                block.id = Some(new_id);
+                block.generation = self.issue_generation(new_id);
+            }
+            self.allocated_blocks.insert(new_id, block);
+            mapping.push((old_id, new_id));
+        }
+        self.next_id = mapping.len();
+        self.rebuild_address_index();
+        mapping
+    }
+
+    /// """Returns the address range touched by live allocations.
+    ///
+    /// The span includes any free gaps between allocations, so memory at or above
+    /// `highest_end` holds no live data.
+    ///
+    /// Returns:
+    ///     Option<(usize, usize)>: The (lowest_start, highest_end) across all allocations, with
+    ///     `highest_end` exclusive, or None if nothing is allocated.
+    /// """
+    pub fn used_span(&self) -> Option<(usize, usize)> {
+        let low = self.allocated_blocks.values().map(|block| block.start).min()?;
+        let high = self.allocated_blocks.values().map(|block| block.start + block.size).max()?;
+        Some((low, high))
+    }
+
+    /// """Checks the allocator's internal invariants.
+    ///
+    /// Every free block must be non-empty, filed under its own size and kept in address order
+    /// within its bucket; every allocation must be filed under its own ID, which must be below
+    /// `next_id`; and the non-empty blocks, free and allocated together with the reserved
+    /// ranges, must tile memory exactly, with no overlaps and no untracked bytes. Zero-size allocations occupy no
+    /// memory and are only checked to lie within it.
+    ///
+    /// Returns:
+    ///     Result<(), String>: Ok(()) if every invariant holds, or a message describing the first violation.
+    /// """
+    pub fn validate(&self) -> Result<(), String> {
+        let mut extents = Vec::new();
+        for (&size, blocks) in &self.free_blocks {
+            for (index, block) in blocks.iter().enumerate() {
+                if block.allocated || block.size != size || size == 0 {
+                    return Err(format!("free block at {} of size {} is filed under size {}", block.start, block.size, size));
+                }
+                if index > 0 && blocks[index - 1].start >= block.start {
+                    return Err(format!("free blocks of size {} are not in address order at {}", size, block.start));
+                }
+                extents.push((block.start, block.size, "free block".to_string()));
+            }
+        }
+        for (&id, block) in &self.allocated_blocks {
+            if !block.allocated || block.id != Some(id) {
+                return Err(format!("allocation at {} is filed under ID {} but records {:?}", block.start, id, block.id));
+            }
+            if id >= self.next_id {
+                return Err(format!("ID {} is not below the next ID {}", id, self.next_id));
+            }
+            if block.start + block.size > self.capacity() {
+                return Err(format!("ID {} at {} of size {} extends past memory", id, block.start, block.size));
+            }
+            if block.size > 0 {
+                extents.push((block.start, block.size, format!("ID {}", id)));
+            }
+        }
+
+        let indexed: BTreeSet<(usize, usize)> = self.allocated_blocks.iter().map(|(&id, block)| (block.start, id)).collect();
+        if indexed != self.by_address {
+            return Err("the address index does not match the allocations".to_string());
+        }
+
+        for &(start, size) in &self.reserved {
+            extents.push((start, size, "reserved range".to_string()));
+        }
+
+        extents.sort_unstable_by_key(|&(start, _, _)| start);
+        let mut next = 0;
+        for (start, size, name) in extents {
+            if start != next {
+                let problem = if start < next { "overlaps the block before it" } else { "follows untracked memory" };
+                return Err(format!("{} at {} {}", name, start, problem));
+            }
+            next = start + size;
+        }
+        if next != self.capacity() {
+            return Err(format!("memory from {} to {} is untracked", next, self.capacity()));
+        }
+        Ok(())
+    }
+
+    /// """Allocates the largest contiguous free region, e.g. as a scratch buffer.
+    ///
+    /// Free blocks are coalesced first so adjacent free space counts as one region; among
+    /// equally large regions the lowest is used. If a maximum allocation size is configured,
+    /// the block is capped at it.
+    ///
+    /// Args:
+    ///     data (&[u8]): The bytes to store, truncated or zero-filled to the block's size.
+    ///
+    /// Returns:
+    ///     Result<(usize, usize), AllocError>: The new allocation's ID and size, or `OutOfMemory`
+    ///     if there is no free memory at all.
+    /// """
+    pub fn insert_max(&mut self, data: &[u8]) -> Result<(usize, usize), AllocError> {
+        self.coalesce();
+        let &largest = self.free_blocks.keys().next_back().ok_or(AllocError::OutOfMemory { requested: 1 })?;
+        let size = self.max_alloc_size.map_or(largest, |max| largest.min(max));
+        let block = self.take_free_block(largest, 0);
+        let start = block.start;
+        Ok((self.allocate_within(block, start, size, data), size))
+    }
+
+    /// """Lists the allocations whose bytes are all zero.
+    ///
+    /// Zero-size allocations hold no bytes and are therefore included.
+    ///
+    /// Returns:
+    ///     Vec<usize>: The IDs of all-zero allocations in ascending order.
+    /// """
+    pub fn zero_allocations(&self) -> Vec<usize> {
+        self.allocated_blocks
+            .iter()
+            .filter(|(_, block)| self.memory[block.start..block.start + block.size].iter().all(|&byte| byte == 0))
+            .map(|(&id, _)| id)
+            .collect()
+    }
+
+    /// """Frees every allocation whose bytes are all zero, e.g. buffers that have been cleared.
+    ///
+    /// Write-protected allocations are freed too, since protection only guards their data.
+    ///
+    /// Returns:
+    ///     usize: The number of allocations freed.
+    /// """
+    pub fn reap_zeros(&mut self) -> usize {
+        let zeros = self.zero_allocations();
+        for &id in &zeros {
+            // The IDs were just listed as allocated, so freeing them cannot fail.
+            let _ = self.delete(id);
+        }
+        zeros.len()
+    }
+
+    /// """Exports the free list exactly as stored, for external defragmentation tooling.
+    ///
+    /// Unlike `free_intervals`, address-adjacent free blocks are not merged.
+    ///
+    /// Returns:
+    ///     Vec<(usize, usize)>: The (start, size) of every free block in ascending address order.
+    /// """
+    pub fn free_list_export(&self) -> Vec<(usize, usize)> {
+        self.free_blocks_ordered()
+    }
+
+    /// """Replaces the free list with externally planned blocks.
+    ///
+    /// The blocks may split or merge the current free space differently, but together they
+    /// must cover exactly the memory that is not allocated: no block may overlap an allocation,
+    /// memory or another block, and no free byte may be left out. Empty blocks are ignored.
+    /// On error the free list is unchanged.
+    ///
+    /// Args:
+    ///     blocks (Vec<(usize, usize)>): The (start, size) of each new free block, in any order.
+    ///
+    /// Returns:
+    ///     Result<(), AllocError>: Ok(()) if the free list was replaced, `RegionNotFree` naming a block
+    ///     that covers memory that is not free, or `UntrackedMemory` naming free memory no block covers.
+    /// """
+    pub fn free_list_import(&mut self, mut blocks: Vec<(usize, usize)>) -> Result<(), AllocError> {
+        blocks.retain(|&(_, size)| size > 0);
+        blocks.sort_unstable();
+
+        // Walk the free intervals in step with the blocks, which must tile each one exactly.
+        let mut pending = blocks.iter().peekable();
+        for (start, end) in self.free_intervals() {
+            let mut next = start;
+            while let Some(&&(block_start, size)) = pending.peek().filter(|&&&(block_start, _)| block_start < end) {
+                if block_start > next {
+                    return Err(AllocError::UntrackedMemory { start: next, size: block_start - next });
+                }
+                if block_start < next || block_start + size > end {
+                    return Err(AllocError::RegionNotFree { start: block_start, size });
+                }
+                next += size;
+                pending.next();
+            }
+            if next < end {
+                return Err(AllocError::UntrackedMemory { start: next, size: end - next });
+            }
+        }
+        if let Some(&(start, size)) = pending.next() {
+            return Err(AllocError::RegionNotFree { start, size });
+        }
+
+        self.free_blocks.clear();
+        for (start, size) in blocks {
+            self.add_free_block(start, size);
+        }
+        Ok(())
+    }
+
+    /// """Computes how many bytes `compact` would copy.
+    ///
+    /// Allocations already at the address compaction would give them are not copied, so a
+    /// result of 0 means memory is already compact.
+    ///
+    /// Returns:
+    ///     usize: The total size of the allocations whose address `compact` would change.
+    /// """
+    pub fn compaction_cost(&self) -> usize {
+        let mut by_address: Vec<&MemoryBlock> = self.allocated_blocks.values().collect();
+        by_address.sort_unstable_by_key(|block| block.start);
+
+        let mut cursor = 0;
+        let mut moved = 0;
+        for block in by_address {
+            cursor = skip_reserved_up(&self.reserved, cursor, block.size);
+            if block.start != cursor {
+                moved += block.size;
+            }
+            cursor += block.size;
+        }
+        moved
+    }
+
+    /// """Returns a generation-checked handle for a live allocation.
+    ///
+    /// Args:
+    ///     id (usize): The unique allocation ID.
+    ///
+    /// Returns:
+    ///     Option<Handle>: The (id, generation) handle, or None if the ID is not allocated.
+    /// """
+    pub fn handle(&self, id: usize) -> Option<Handle> {
+        self.allocated_blocks.get(&id).map(|block| (id, block.generation))
+    }
+
+    /// """Resolves a handle to its live allocation.
+    ///
+    /// Args:
+    ///     handle (Handle): The (id, generation) handle to check.
+    ///
+    /// Returns:
+    ///     Result<&MemoryBlock, AllocError>: The allocation, `StaleHandle` if the ID was freed or
+    ///     reissued since the handle was taken, or `UnknownId` if the ID was never issued.
+    /// """
+    fn resolve_handle(&self, (id, generation): Handle) -> Result<&MemoryBlock, AllocError> {
+        match self.allocated_blocks.get(&id) {
+            Some(block) if block.generation == generation => Ok(block),
+            _ if self.generations.contains_key(&id) => Err(AllocError::StaleHandle { id, generation }),
+            _ => Err(AllocError::UnknownId(id)),
+        }
+    }
+
+    /// """Finds the data of an allocation through a generation-checked handle.
+    ///
+    /// Unlike `find`, a handle whose allocation was freed and whose ID was later reissued is
+    /// rejected rather than returning the newer allocation's data.
+    ///
+    /// Args:
+    ///     handle (Handle): The (id, generation) handle from `handle`.
+    ///
+    /// Returns:
+    ///     Result<&[u8], AllocError>: The allocation's data, `StaleHandle` if the handle outlived
+    ///     its allocation, or `UnknownId` if the ID was never issued.
+    /// """
+    pub fn find_handle(&self, handle: Handle) -> Result<&[u8], AllocError> {
+        let block = self.resolve_handle(handle)?;
+        Ok(&self.memory[block.start..block.start + block.size])
+    }
+
+    /// """Updates an allocation like `update`, through a generation-checked handle.
+    ///
+    /// Args:
+    ///     handle (Handle): The (id, generation) handle from `handle`.
+    ///     new_data (&[u8]): A byte slice containing the new data.
+    ///
+    /// Returns:
+    ///     Result<(), AllocError>: As for `update`, or `StaleHandle` if the handle outlived its allocation.
+    /// """
+    pub fn update_handle(&mut self, handle: Handle, new_data: &[u8]) -> Result<(), AllocError> {
+        self.resolve_handle(handle)?;
+        self.update(handle.0, new_data)
+    }
+
+    /// """Finds the address and data of an allocation like `find_with_addr`, through a
+    /// generation-checked handle.
+    ///
+    /// Args:
+    ///     handle (Handle): The (id, generation) handle from `handle`.
+    ///
+    /// Returns:
+    ///     Result<(usize, &[u8]), AllocError>: The starting address and data, `StaleHandle` if the
+    ///     handle outlived its allocation, or `UnknownId` if the ID was never issued.
+    /// """
+    pub fn find_with_addr_handle(&self, handle: Handle) -> Result<(usize, &[u8]), AllocError> {
+        let block = self.resolve_handle(handle)?;
+        Ok((block.start, &self.memory[block.start..block.start + block.size]))
+    }
+
+    /// """Frees an allocation like `delete`, through a generation-checked handle.
+    ///
+    /// A handle kept past a free cannot free whichever allocation later reuses its ID.
+    ///
+    /// Args:
+    ///     handle (Handle): The (id, generation) handle from `handle`.
+    ///
+    /// Returns:
+    ///     Result<(), AllocError>: Ok(()) if the block was freed, `StaleHandle` if the handle
+    ///     outlived its allocation, or `UnknownId` if the ID was never issued.
+    /// """
+    pub fn delete_handle(&mut self, handle: Handle) -> Result<(), AllocError> {
+        self.resolve_handle(handle)?;
+        self.delete(handle.0)
+    }
+
+    /// """Finds the allocations on either side of an allocation in address order.
+    ///
+    /// Free gaps are skipped, so the neighbours need not be physically touching. A zero-size
+    /// allocation sharing a start address with another block is ordered before it.
+    ///
+    /// Args:
+    ///     id (usize): The unique allocation ID to look around.
+    ///
+    /// Returns:
+    ///     (Option<usize>, Option<usize>): The IDs of the nearest allocations at lower and higher
+    ///     addresses; both are None if the ID is not allocated.
+    /// """
+    pub fn neighbors(&self, id: usize) -> (Option<usize>, Option<usize>) {
+        let Some(block) = self.allocated_blocks.get(&id) else {
+            return (None, None);
+        };
+        let order = |block: &MemoryBlock, id: usize| (block.start, block.size, id);
+        let key = order(block, id);
+        let keys = self.allocated_blocks.iter().map(|(&other, block)| order(block, other));
+        let below = keys.clone().filter(|&other| other < key).max().map(|(_, _, id)| id);
+        let above = keys.filter(|&other| other > key).min().map(|(_, _, id)| id);
+        (below, above)
+    }
+
+    /// """Permanently withdraws an address range from allocation, e.g. for memory-mapped IO.
+    ///
+    /// The range gets no ID, is never handed out by any insert and cannot be freed; compaction
+    /// and `reset` leave it in place, and `dump` lists it as RESERVED. Reserving an empty range
+    /// does nothing.
+    ///
+    /// Args:
+    ///     start (usize): The first address of the range.
+    ///     size (usize): The length of the range in bytes.
+    ///
+    /// Returns:
+    ///     Result<(), AllocError>: Ok(()) if the range is now reserved, or `RegionNotFree` if any of
+    ///     it is allocated, already reserved or outside memory.
+    /// """
+    pub fn reserve_reserved(&mut self, start: usize, size: usize) -> Result<(), AllocError> {
+        if size == 0 {
+            return Ok(());
+        }
+        self.take_free_range(start, size)?;
+        let index = self.reserved.partition_point(|&(other, _)| other < start);
+        self.reserved.insert(index, (start, size));
+        Ok(())
+    }
+
+    /// """Counts how many more `size`-byte allocations would succeed right now.
+    ///
+    /// Allocation is simulated on a copy of the free list, taking the best-fitting block and
+    /// splitting off the remainder until nothing fits. Each block yields the same number of
+    /// allocations whichever order they are placed in, so the count does not depend on the
+    /// configured strategy or selector. Unlike `free_bytes / size`, this accounts for free space
+    /// that is too fragmented to hold a whole record.
+    ///
+    /// Args:
+    ///     size (usize): The size of each simulated allocation in bytes.
+    ///
+    /// Returns:
+    ///     usize: The number of allocations that fit; 0 if `size` exceeds the configured
+    ///     maximum, and `usize::MAX` for zero-size allocations, which always fit.
+    /// """
+    pub fn remaining_capacity_for(&self, size: usize) -> usize {
+        if size == 0 {
+            return usize::MAX;
+        }
+        if self.check_alloc_size(size).is_err() {
+            return 0;
+        }
+        let mut free_blocks = self.free_blocks.clone();
+        let mut count = 0;
+        while let Some((key, index)) = best_fit_in(&free_blocks, size) {
+            let bucket = free_blocks.get_mut(&key).unwrap();
+            let mut block = bucket.remove(index);
+            if bucket.is_empty() {
+                free_blocks.remove(&key);
+            }
+            if block.size > size {
+                block.start += size;
+                block.size -= size;
+                let bucket = free_blocks.entry(block.size).or_default();
+                let index = bucket.partition_point(|other| other.start < block.start);
+                bucket.insert(index, block);
+            }
+            count += 1;
+        }
+        count
+    }
+
+    /// """Times allocating and then freeing `count` blocks of `size` bytes.
+    ///
+    /// All `count` inserts run first, using the configured strategy, followed by a delete of
+    /// each block that was allocated; inserts that fail still count towards the time. The
+    /// manager is then restored exactly as it was, so benchmarking leaves no trace.
+    ///
+    /// Args:
+    ///     size (usize): The size of each allocation in bytes.
+    ///     count (usize): The number of blocks to allocate and free.
+    ///
+    /// Returns:
+    ///     Duration: The wall-clock time taken by the inserts and deletes.
+    /// """
+    pub fn bench_alloc(&mut self, size: usize, count: usize) -> Duration {
+        let saved = self.clone();
+        let started = Instant::now();
+        let ids: Vec<usize> = (0..count).filter_map(|_| self.insert(size, &[]).ok()).collect();
+        for id in ids {
+            // The IDs were just allocated, so freeing them cannot fail.
+            let _ = self.delete(id);
+        }
+        let elapsed = started.elapsed();
+        *self = saved;
+        elapsed
+    }
+
+    /// """Predicts the free block best-fit placement would choose for a request, without allocating.
+    ///
+    /// This is the block `insert` uses under the default `BestFit` strategy with no custom
+    /// selector: the smallest free block that can hold `size` bytes, the lowest-addressed on ties.
+    /// In lazy coalescing mode `insert` may also merge free blocks and retry when this is None.
+    ///
+    /// Args:
+    ///     size (usize): The number of bytes required.
+    ///
+    /// Returns:
+    ///     Option<(usize, usize)>: The (start, size) of the chosen free block, or None if no free
+    ///     block is large enough.
+    /// """
+    pub fn best_fit_block(&self, size: usize) -> Option<(usize, usize)> {
+        let (key, index) = self.find_best_fit(size)?;
+        let block = &self.free_blocks[&key][index];
+        Some((block.start, block.size))
+    }
+}
+
+/// """Finds the smallest free block that can hold `size` bytes in a size-keyed free list.
+///
+/// Args:
+///     free_blocks (&BTreeMap<usize, Vec<MemoryBlock>>): Free blocks bucketed by size.
+///     size (usize): The number of bytes required.
+///
+/// Returns:
+///     Option<(usize, usize)>: The size key and index within its bucket of the chosen block,
+///     or None if no free block is large enough.
+fn best_fit_in(free_blocks: &BTreeMap<usize, Vec<MemoryBlock>>, size: usize) -> Option<(usize, usize)> {
+    // Iterate over free block sizes starting from `size`
+    for (&free_size, blocks) in free_blocks.range(size..) {
+        if let Some(index) = blocks.iter().position(|block| block.size >= size) {
+            return Some((free_size, index));
+        }
+    }
+    None
+}
+
+/// """Lists the gaps in memory not covered by any of the given ranges.
+///
+/// Args:
+///     covered (Vec<(usize, usize)>): The (start, size) of each occupied range, in any order.
+///     capacity (usize): The size of memory in bytes.
+///
+/// Returns:
+///     Vec<(usize, usize)>: The (start, size) of each uncovered gap in address order.
+fn uncovered_gaps(mut covered: Vec<(usize, usize)>, capacity: usize) -> Vec<(usize, usize)> {
+    covered.sort_unstable();
+    let mut gaps = Vec::new();
+    let mut cursor = 0;
+    for (start, size) in covered {
+        if start > cursor {
+            gaps.push((cursor, start - cursor));
+        }
+        cursor = cursor.max(start + size);
+    }
+    if cursor < capacity {
+        gaps.push((cursor, capacity - cursor));
+    }
+    gaps
+}
+
+/// """Finds the lowest placement at or above `cursor` that avoids every reserved range.
+///
+/// Args:
+///     reserved (&[(usize, usize)]): The reserved (start, size) ranges in address order.
+///     cursor (usize): The lowest acceptable start.
+///     size (usize): The size of the block being placed.
+///
+/// Returns:
+///     usize: The start at which `size` bytes overlap no reserved range.
+fn skip_reserved_up(reserved: &[(usize, usize)], mut cursor: usize, size: usize) -> usize {
+    for &(start, length) in reserved {
+        if start < cursor + size && cursor < start + length {
+            cursor = start + length;
+        }
+    }
+    cursor
+}
+
+/// """Finds the highest placement ending at or below `end` that avoids every reserved range.
+///
+/// Args:
+///     reserved (&[(usize, usize)]): The reserved (start, size) ranges in address order.
+///     end (usize): The highest acceptable end (exclusive).
+///     size (usize): The size of the block being placed.
+///
+/// Returns:
+///     usize: The end at which `size` bytes overlap no reserved range.
+fn skip_reserved_down(reserved: &[(usize, usize)], mut end: usize, size: usize) -> usize {
+    for &(start, length) in reserved.iter().rev() {
+        if start < end && end - size < start + length {
+            end = start;
+        }
+    }
+    end
+}
+
+/// """Calculates the smallest power of two that is greater than or equal to a given request size.
+///
+/// This helper function is useful when ensuring that memory allocations are aligned or sized
 /// to the nearest power of two.
 ///
 /// Args:
@@ -264,6 +3814,7 @@ impl MemoryManager {
 ///
 /// Returns:
 ///     usize: The smallest power of two that is greater than or equal to the request.
+#[allow(dead_code)]
 fn next_largest(request: usize) -> usize {
     let mut power = 1;
     while power < request {
@@ -271,3 +3822,256 @@ fn next_largest(request: usize) -> usize {
     }
     power
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compact_by_age_falls_back_when_reserved_ranges_block_age_order() {
+        let mut mm = MemoryManager::with_capacity(10);
+        mm.insert(5, b"").unwrap();
+        mm.reserve_reserved(5, 1).unwrap();
+        mm.insert(3, b"").unwrap();
+        mm.delete(0).unwrap();
+        mm.insert(5, b"").unwrap();
+
+        mm.compact_by_age();
+        assert!(mm.validate().is_ok());
+        assert_eq!(mm.stats().allocation_count, 2);
+    }
+
+    #[test]
+    fn reserved_ranges_raise_the_fragmentation_floor() {
+        let mut mm = MemoryManager::with_capacity(10);
+        mm.reserve_reserved(4, 1).unwrap();
+        mm.insert(3, b"").unwrap();
+
+        // Compaction cannot merge the byte below the reserved range with the space above it.
+        assert!(mm.fragmentation_floor() > 0.0);
+        assert!(!mm.needs_compaction(6));
+
+        mm.reset();
+        assert!(mm.is_pristine());
+    }
+
+    #[test]
+    fn cloned_managers_share_a_selector_that_is_not_sync() {
+        // A Cell is Send but not Sync, so this selector only fits the Send-only Selector type.
+        let declined = std::cell::Cell::new(0);
+        let mut mm = MemoryManager::with_capacity(64);
+        mm.set_selector(Box::new(move |_| {
+            declined.set(declined.get() + 1);
+            None
+        }));
+        let mut forked = mm.clone();
+
+        assert!(matches!(forked.insert(8, b""), Err(AllocError::OutOfMemory { .. })));
+        assert!(matches!(mm.insert(8, b""), Err(AllocError::OutOfMemory { .. })));
+    }
+
+    #[test]
+    fn realloc_shrinks_in_place() {
+        let mut mm = MemoryManager::with_capacity(64);
+        mm.insert(4, b"").unwrap();
+        let id = mm.insert(8, b"abcdefgh").unwrap();
+
+        mm.realloc(id, 3).unwrap();
+        assert_eq!(mm.find_with_addr(id), Some((4, &b"abc"[..])));
+        assert!(mm.validate().is_ok());
+    }
+
+    #[test]
+    fn free_buckets_stay_in_address_order() {
+        let mut mm = MemoryManager::with_capacity(64);
+        for _ in 0..8 {
+            mm.insert(8, b"").unwrap();
+        }
+        // Every other block, highest first, so no two frees coalesce.
+        for id in [6, 2, 4, 0] {
+            mm.delete(id).unwrap();
+        }
+
+        let starts: Vec<usize> = mm.free_blocks[&8].iter().map(|block| block.start).collect();
+        assert_eq!(starts, [0, 16, 32, 48]);
+    }
+
+    #[test]
+    fn protected_blocks_reject_writes_until_unprotected() {
+        let mut mm = MemoryManager::with_capacity(64);
+        let id = mm.insert(4, b"abcd").unwrap();
+        mm.protect(id).unwrap();
+
+        assert_eq!(mm.update(id, b"wxyz"), Err(AllocError::Protected(id)));
+        assert_eq!(mm.replace(id, b"wxyz"), Err(AllocError::Protected(id)));
+        assert_eq!(mm.find(id), Some(&b"abcd"[..]));
+
+        mm.unprotect(id).unwrap();
+        mm.update(id, b"wxyz").unwrap();
+        assert_eq!(mm.find(id), Some(&b"wxyz"[..]));
+    }
+
+    #[test]
+    fn compact_high_leaves_the_free_block_at_address_zero() {
+        let mut mm = MemoryManager::with_capacity(64);
+        for _ in 0..4 {
+            mm.insert(8, b"").unwrap();
+        }
+        mm.delete(0).unwrap();
+        mm.delete(2).unwrap();
+
+        mm.compact_high();
+        assert_eq!(mm.free_blocks_ordered(), [(0, 48)]);
+        assert_eq!(mm.find_with_addr(1).unwrap().0, 48);
+        assert_eq!(mm.find_with_addr(3).unwrap().0, 56);
+    }
+
+    #[test]
+    fn realloc_respects_the_maximum_allocation_size() {
+        let mut mm = MemoryManager::builder().capacity(2048).max_alloc_size(8).build();
+        let id = mm.insert(8, b"").unwrap();
+
+        assert_eq!(mm.realloc(id, 1000), Err(AllocError::AllocationTooLarge { requested: 1000, max: 8 }));
+        assert_eq!(mm.find(id).unwrap().len(), 8);
+    }
+
+    #[test]
+    fn stats_report_the_fragmentation_floor() {
+        let mut mm = MemoryManager::with_capacity(64);
+        mm.reserve_reserved(16, 8).unwrap();
+        mm.insert(12, b"").unwrap();
+
+        // 4 free bytes below the reserved range cannot join the 40 above it.
+        let stats = mm.stats();
+        assert_eq!(stats.largest_gap_after_compaction, 40);
+        assert_eq!(mm.fragmentation_floor(), 4.0 / 44.0);
+        assert!(stats.to_string().contains("(floor 0.09 after compaction)"));
+    }
+
+    #[test]
+    fn allocations_in_range_lists_overlapping_ids_by_address() {
+        let mut mm = MemoryManager::with_capacity(64);
+        for size in [8, 0, 8, 8] {
+            mm.insert(size, b"").unwrap();
+        }
+        mm.delete(2).unwrap();
+        mm.insert(4, b"").unwrap();
+
+        assert_eq!(mm.allocations_in_range(4, 20), [0, 4, 3]);
+        assert_eq!(mm.allocations_in_range(8, 8), Vec::<usize>::new());
+
+        // The address index follows blocks as they move.
+        mm.move_to(0, 40).unwrap();
+        assert_eq!(mm.allocations_in_range(0, 24), [4, 3]);
+        mm.compact();
+        assert_eq!(mm.allocations_in_range(0, 64), [4, 3, 0]);
+        assert!(mm.validate().is_ok());
+    }
+
+    #[test]
+    fn remaining_capacity_counts_whole_records_per_free_block() {
+        let mut mm = MemoryManager::with_capacity(64);
+        for _ in 0..4 {
+            mm.insert(8, b"").unwrap();
+        }
+        mm.delete(1).unwrap();
+        // Free: 8 bytes at 8 and 32 bytes at 32; a selector must not affect the count.
+        mm.set_selector(Box::new(|_| None));
+
+        assert_eq!(mm.remaining_capacity_for(8), 5);
+        assert_eq!(mm.remaining_capacity_for(12), 2);
+        assert_eq!(mm.stats().free_block_count, 2);
+    }
+
+    #[test]
+    fn move_to_rejects_a_zero_size_move_past_the_end() {
+        let mut mm = MemoryManager::with_capacity(64);
+        let id = mm.insert(0, b"").unwrap();
+
+        assert_eq!(mm.move_to(id, 1000), Err(AllocError::RegionNotFree { start: 1000, size: 0 }));
+        assert_eq!(mm.move_to(id, usize::MAX), Err(AllocError::RegionNotFree { start: usize::MAX, size: 0 }));
+        mm.move_to(id, 64).unwrap();
+        assert_eq!(mm.find_with_addr(id).unwrap().0, 64);
+    }
+
+    #[test]
+    fn failed_import_leaves_the_manager_unchanged() {
+        let mut mm = MemoryManager::builder().capacity(64).strategy(AllocStrategy::Lifo).build();
+        for _ in 0..4 {
+            mm.insert(16, b"").unwrap();
+        }
+        mm.delete(0).unwrap();
+        mm.delete(2).unwrap();
+        let before = mm.stats();
+
+        let mut other = MemoryManager::with_capacity(128);
+        other.insert(16, b"").unwrap();
+        other.insert(64, b"").unwrap();
+        assert_eq!(mm.import_from(&other), Err(AllocError::OutOfMemory { requested: 64 }));
+
+        assert_eq!(mm.stats(), before);
+        // The most recently freed block is still the one Lifo hands out next.
+        let id = mm.insert(16, b"").unwrap();
+        assert_eq!(mm.find_with_addr(id).unwrap().0, 32);
+    }
+
+    #[test]
+    fn reserve_ends_spans_split_free_blocks() {
+        let mut mm = MemoryManager::with_capacity(32);
+        mm.free_list_import(vec![(0, 16), (16, 16)]).unwrap();
+
+        assert_eq!(mm.reserve_ends(4, 20), Ok((0, 1, 8)));
+        assert!(mm.validate().is_ok());
+    }
+
+    #[test]
+    fn reserve_ends_respects_the_maximum_allocation_size() {
+        let mut mm = MemoryManager::builder().capacity(64).max_alloc_size(8).build();
+
+        assert_eq!(mm.reserve_ends(4, 16), Err(AllocError::AllocationTooLarge { requested: 16, max: 8 }));
+        assert!(mm.is_pristine());
+    }
+
+    #[test]
+    fn every_allocator_merges_lazily_freed_blocks() {
+        // Four adjacent 8-byte blocks freed in lazy mode leave 32 free bytes in four pieces.
+        let lazily_freed = || {
+            let mut mm = MemoryManager::builder().capacity(40).coalesce_mode(CoalesceMode::Lazy).build();
+            for _ in 0..5 {
+                mm.insert(8, b"").unwrap();
+            }
+            for id in 0..4 {
+                mm.delete(id).unwrap();
+            }
+            mm
+        };
+
+        assert!(lazily_freed().insert(32, b"").is_ok());
+        assert!(lazily_freed().insert_high(32, b"").is_ok());
+        assert!(lazily_freed().insert_within(32, 0, b"").is_ok());
+        assert!(lazily_freed().reserve_aligned(32, 32).is_ok());
+        assert!(lazily_freed().first_fit_aligned(32, 16, b"").is_ok());
+        assert!(lazily_freed().insert_near(32, 20, b"").is_ok());
+        assert!(lazily_freed().realloc(4, 32).is_ok());
+    }
+
+    #[test]
+    fn stale_handles_are_rejected_by_every_handle_method() {
+        let mut mm = MemoryManager::with_capacity(64);
+        let id = mm.insert(4, b"old!").unwrap();
+        let stale = mm.handle(id).unwrap();
+        mm.delete_handle(stale).unwrap();
+        mm.reset_ids().unwrap();
+        let reissued = mm.insert(4, b"new!").unwrap();
+        assert_eq!(reissued, id);
+
+        let stale_error = AllocError::StaleHandle { id, generation: stale.1 };
+        assert_eq!(mm.find_handle(stale), Err(stale_error.clone()));
+        assert_eq!(mm.find_with_addr_handle(stale), Err(stale_error.clone()));
+        assert_eq!(mm.update_handle(stale, b"bad!"), Err(stale_error.clone()));
+        assert_eq!(mm.delete_handle(stale), Err(stale_error));
+
+        let fresh = mm.handle(reissued).unwrap();
+        assert_eq!(mm.find_with_addr_handle(fresh), Ok((0, &b"new!"[..])));
+    }
+}
@@ -13,8 +13,6 @@ use memory_manager::proc::proc::process_file;
 /// Returns:
 ///     () -- This function does not return a value. It exits after execution.
 /// """
-
-
 fn main() {
     let mut memory_manager = MemoryManager::new();
     let file_path = "commands.cmmd";
@@ -1,121 +1,1356 @@
 // memory_manager.rs or mod.rs (if you place this in a folder named memory_manager)
 
-use crate::MemoryManager;
-use std::collections::BTreeMap;
-use std::io::{self, BufRead};
-use std::fs::File;
-use std::path::Path;
+use crate::{AddressRadix, AllocError, AllocStrategy, LogFormat, ManagerConfig, MemoryManager, MemoryStats, UnknownPolicy};
 
 /// """Module containing process-related functions for the memory manager.
 ///
 /// This module defines functions to process command files which control memory allocation
-/// and related operations, such as INSERT, DELETE, FIND, READ, UPDATE, and DUMP.
+/// and related operations, such as INSERT, INSERTD, DELETE, FIND, READ, UPDATE, DUMP, STATS, SLIVERS, ASSERT, RADIX, DEFRAG, PROTECT, DEDUP, FLUSH, LOGFMT, CONFIG, LOADBLOB, SAVEBLOB, BENCH, FORK, and SWITCH.
+#[allow(clippy::module_inception)]
 pub mod proc {
-    use super::MemoryManager;
-    use std::io::{self, BufRead};
+    use super::{
+        AddressRadix, AllocError, AllocStrategy, LogFormat, ManagerConfig, MemoryManager, MemoryStats, UnknownPolicy,
+    };
+    use std::collections::HashMap;
+    use std::io::{self, BufRead, Read, Write};
     use std::fs::File;
     use std::path::Path;
+    use std::time::Duration;
+
+    /// """Summary of the outcomes of a processed command sequence.
+    ///
+    /// Attributes:
+    ///     commands (usize): The number of non-empty command lines processed.
+    ///     allocations (usize): The number of successful INSERT/INSERTD/LOADBLOB commands.
+    ///     failed_allocations (usize): The number of INSERT/INSERTD/LOADBLOB commands that ran out of memory.
+    ///     deletions (usize): The number of successful DELETE commands.
+    ///     errors (usize): The number of malformed or unknown commands and operations on unknown IDs.
+    ///     failed_assertions (usize): The number of ASSERT commands whose expectation did not hold.
+    #[derive(Debug, Clone, Default, PartialEq, Eq)]
+    pub struct ProcessReport {
+        pub commands: usize,
+        pub allocations: usize,
+        pub failed_allocations: usize,
+        pub deletions: usize,
+        pub errors: usize,
+        pub failed_assertions: usize,
+    }
+
+    impl ProcessReport {
+        /// """Updates the counters with the outcome of one command.
+        ///
+        /// Args:
+        ///     result (&CommandResult): The outcome to record.
+        ///
+        /// Returns:
+        ///     None
+        /// """
+        fn record(&mut self, result: &CommandResult) {
+            if *result == CommandResult::Blank {
+                return;
+            }
+            self.commands += 1;
+            match result {
+                CommandResult::Allocated { .. } => self.allocations += 1,
+                CommandResult::AllocationFailed(_) => self.failed_allocations += 1,
+                CommandResult::Deleted { .. } => self.deletions += 1,
+                CommandResult::AssertionFailed(_) => self.failed_assertions += 1,
+                CommandResult::DeleteFailed(_) | CommandResult::Error(_) => self.errors += 1,
+                _ => {}
+            }
+        }
+    }
+
+    /// """The outcome of executing a single command line.
+    ///
+    /// Variants:
+    ///     Blank: The line was empty and is not counted as a command.
+    ///     Allocated: INSERT/INSERTD/LOADBLOB stored data under `id` in the block at `start`.
+    ///     AllocationFailed: INSERT/INSERTD/LOADBLOB could not allocate memory.
+    ///     Deleted: DELETE freed `id`, returning the block at `start` to free memory.
+    ///     DeleteFailed: DELETE could not free the ID, e.g. `UnknownId` for a double free.
+    ///     Found: FIND located the given ID at `start` holding `data`.
+    ///     NotFound: FIND did not locate the given ID.
+    ///     Read: READ returned the data of the given ID.
+    ///     Updated: UPDATE wrote `data` into the given ID.
+    ///     Dumped: DUMP was requested; the dump reflects the manager's current state.
+    ///     Stats: STATS returned the given statistics.
+    ///     Slivers: SLIVERS listed the free gaps smaller than `below`.
+    ///     RadixSet: RADIX switched address formatting.
+    ///     Defragmented: DEFRAG SMART moved the given number of blocks.
+    ///     Protected: PROTECT made the given ID read-only.
+    ///     Deduplicated: DEDUP freed `freed` duplicate allocations, reclaiming `bytes`.
+    ///     Flushed: FLUSH asked for buffered output to be written out.
+    ///     LogFormatSet: LOGFMT switched how allocation and free events are written.
+    ///     Saved: SAVEBLOB wrote the data of `id` to the file at `path`.
+    ///     Benchmarked: BENCH allocated and freed `count` blocks of `size` bytes in `elapsed`.
+    ///     Config: CONFIG returned the manager's settings.
+    ///     Forked: FORK stored a copy of the active manager under the given name.
+    ///     Switched: SWITCH made the named manager active.
+    ///     AssertionPassed: ASSERT held; the message describes what was checked.
+    ///     AssertionFailed: ASSERT did not hold; the message describes the mismatch.
+    ///     Error: The command was malformed, had an unparsable argument, was unknown, or targeted an unknown ID.
+    #[derive(Debug, Clone, PartialEq)]
+    pub enum CommandResult {
+        Blank,
+        Allocated { id: usize, start: usize, size: usize },
+        AllocationFailed(AllocError),
+        Deleted { id: usize, start: usize, size: usize },
+        DeleteFailed(AllocError),
+        Found { id: usize, start: usize, data: Vec<u8> },
+        NotFound(usize),
+        Read { id: usize, data: Vec<u8> },
+        Updated { id: usize, data: Vec<u8> },
+        Dumped,
+        Stats(MemoryStats),
+        Slivers { below: usize, slivers: Vec<(usize, usize)> },
+        RadixSet(AddressRadix),
+        Defragmented(usize),
+        Protected(usize),
+        Deduplicated { freed: usize, bytes: usize },
+        Flushed,
+        LogFormatSet(LogFormat),
+        Saved { id: usize, path: String },
+        Benchmarked { size: usize, count: usize, elapsed: Duration },
+        Config(ManagerConfig),
+        Forked(String),
+        Switched(String),
+        AssertionPassed(String),
+        AssertionFailed(String),
+        Error(String),
+    }
 
     /// """Processes a file containing commands to manipulate the memory manager.
     ///
-    /// The supported commands are: INSERT, DELETE, FIND, READ, UPDATE, and DUMP.
+    /// The supported commands are: INSERT, INSERTD, DELETE, FIND, READ, UPDATE, DUMP, STATS, SLIVERS, ASSERT, RADIX, DEFRAG, PROTECT, DEDUP, FLUSH, LOGFMT, CONFIG, LOADBLOB, SAVEBLOB, BENCH, FORK, and SWITCH.
+    ///
+    /// `INSERT <size> <data>` stores data in a block of `size` bytes, while `INSERT <size>`
+    /// without data reserves `size` zeroed bytes.
+    ///
+    /// `INSERTD <data>` is the recommended way to store data: the allocation size is taken
+    /// from the data itself. The data may be double-quoted to include spaces, with `\"` and
+    /// `\\` as escapes, e.g. `INSERTD "hello world"`; unquoted data must be a single word.
+    ///
+    /// `DELETE <id>` on an ID that is not allocated, such as a double free, is reported and
+    /// processing continues, unless the manager's `UnknownPolicy` is `Fail`, in which case
+    /// processing stops with an error naming the line.
+    ///
+    /// `ASSERT FREE <n>`, `ASSERT ALLOCATED <n>` and `ASSERT USED <bytes>` check the number of
+    /// free blocks, the number of allocations, and the allocated bytes against `stats()`.
+    /// `ASSERT DATA <id> <bytes>` checks that the allocation holds exactly `<bytes>`.
+    /// A mismatch is reported and, when the manager is in strict mode, stops processing with
+    /// an error naming the failing line.
+    ///
+    /// `RADIX hex|dec` switches how addresses are printed by subsequent commands.
+    ///
+    /// `DEFRAG SMART` relocates only the allocations that block free regions from merging.
+    ///
+    /// `PROTECT <id>` makes an allocation read-only, so later UPDATEs to it fail.
+    ///
+    /// `DEDUP` frees every allocation whose contents duplicate a lower-ID allocation.
+    ///
+    /// `FLUSH` flushes any output buffered so far. Output is also flushed once all
+    /// commands have run.
+    ///
+    /// `LOGFMT human|csv` switches how INSERT, INSERTD and DELETE report their events; in
+    /// CSV mode each event is an `op,id,start,size,timestamp` row.
+    ///
+    /// `LOADBLOB <path>` stores the entire contents of a file in a block of exactly its size,
+    /// so binary assets need not be embedded in the command file. `SAVEBLOB <id> <path>` writes
+    /// an allocation's bytes back out to a file for inspection with external tools.
+    ///
+    /// `BENCH <size> <count>` times allocating and freeing `count` blocks of `size` bytes with
+    /// the configured strategy and prints the total and per-pair time; memory is left unchanged.
+    ///
+    /// `CONFIG` prints the manager's settings, e.g. to check how a builder chain set it up.
+    ///
+    /// `FORK <name>` saves a deep copy of the current manager under `name`, and `SWITCH <name>`
+    /// runs subsequent commands against that copy, so one trace can explore diverging futures.
+    /// The manager passed in is named `main`; it is the one left updated when processing ends.
+    ///
+    /// If the manager was built with `auto_compact_every` or `auto_compact_threshold`, memory
+    /// is compacted between commands when due and a note is printed. If it was built with
+    /// `max_total_operations`, processing stops with an error at the first command past the limit.
+    ///
+    /// A file with no commands, e.g. an empty one, prints "No commands processed".
     ///
     /// Args:
     ///     file_path (str): The path to the command file.
     ///     memory_manager (MemoryManager): A mutable reference to the memory manager instance.
     ///
     /// Returns:
-    ///     io::Result<()>: Ok(()) if processing was successful; otherwise, an I/O error.
-    /// """
-    pub fn process_file(file_path: &str, memory_manager: &mut MemoryManager) -> io::Result<()> {
-        if let Ok(lines) = read_lines(file_path) {
-            for line in lines.flatten() {
-                println!("Processing line: {}", line);
-                let tokens: Vec<&str> = line.split_whitespace().collect();
-                if tokens.is_empty() {
-                    continue;
-                }
-                match tokens[0] {
-                    "INSERT" => {
-                        if tokens.len() < 3 {
-                            println!("Error: Invalid INSERT command");
-                            continue;
-                        }
-                        // Parse the size and use the third token as data (as bytes).
-                        if let (Ok(size), data) = (tokens[1].parse::<usize>(), tokens[2].as_bytes()) {
-                            if let Some(id) = memory_manager.insert(size, data) {
-                                println!("Allocated ID: {}", id);
-                            } else {
-                                println!("Memory allocation failed");
-                            }
-                        }
-                    }
-                    "DELETE" => {
-                        if tokens.len() < 2 {
-                            println!("Error: Invalid DELETE command");
-                            continue;
-                        }
-                        if let Ok(id) = tokens[1].parse::<usize>() {
-                            memory_manager.delete(id);
-                        }
-                    }
-                    "FIND" => {
-                        if tokens.len() < 2 {
-                            println!("Error: Invalid FIND command");
-                            continue;
-                        }
-                        if let Ok(id) = tokens[1].parse::<usize>() {
-                            if let Some(data) = memory_manager.find(id) {
-                                println!("Data at {}: {:?}", id, data);
-                            } else {
-                                println!("Nothing at {}", id);
-                            }
-                        }
-                    }
-                    "READ" => {
-                        if tokens.len() == 2 {
-                            if let Ok(id) = tokens[1].parse::<usize>() {
-                                memory_manager.read(id);
-                            } else {
-                                println!("Invalid READ command format");
-                            }
-                        }
-                    }
-                    "UPDATE" => {
-                        if tokens.len() < 3 {
-                            println!("Error: Invalid UPDATE command");
-                            continue;
-                        }
-                        if let (Ok(id), new_data) = (tokens[1].parse::<usize>(), tokens[2].as_bytes()) {
-                            memory_manager.update(id, new_data);
-                        }
-                    }
-                    "DUMP" => {
-                        memory_manager.dump();
-                    }
-                    _ => {
-                        println!("Error: Unknown command `{}`", tokens[0]);
+    ///     io::Result<ProcessReport>: A summary of the command outcomes, all zero if the file is
+    ///     empty or cannot be opened; otherwise, an I/O error.
+    /// """
+    pub fn process_file(file_path: &str, memory_manager: &mut MemoryManager) -> io::Result<ProcessReport> {
+        match read_lines(file_path) {
+            Ok(lines) => process_commands(lines.map_while(Result::ok), memory_manager),
+            Err(_) => Ok(ProcessReport::default()),
+        }
+    }
+
+    /// """Processes a sequence of commands, printing all output to stdout.
+    ///
+    /// Args:
+    ///     commands (IntoIterator<Item = String>): The command lines to process, in order.
+    ///     memory_manager (MemoryManager): A mutable reference to the memory manager instance.
+    ///
+    /// Returns:
+    ///     io::Result<ProcessReport>: A summary of the command outcomes, or an error writing to stdout.
+    /// """
+    pub fn process_commands<I>(commands: I, memory_manager: &mut MemoryManager) -> io::Result<ProcessReport>
+    where
+        I: IntoIterator<Item = String>,
+    {
+        let stdout = io::stdout();
+        process_commands_to(commands, memory_manager, &mut stdout.lock())
+    }
+
+    /// """Processes a sequence of commands and captures all output in a string.
+    ///
+    /// This is useful for asserting on the exact textual output of a trace without
+    /// capturing the process's stdout.
+    ///
+    /// Args:
+    ///     commands (IntoIterator<Item = String>): The command lines to process, in order.
+    ///     memory_manager (MemoryManager): A mutable reference to the memory manager instance.
+    ///
+    /// Returns:
+    ///     (ProcessReport, String): A summary of the command outcomes and everything that was output.
+    /// """
+    pub fn process_commands_captured<I>(commands: I, memory_manager: &mut MemoryManager) -> (ProcessReport, String)
+    where
+        I: IntoIterator<Item = String>,
+    {
+        let mut output = Vec::new();
+        let mut report = ProcessReport::default();
+        // Writing to a Vec cannot fail, so an error here is a strict-mode failure.
+        if let Err(err) = run_commands(commands, memory_manager, &mut output, &mut report) {
+            let _ = writeln!(output, "Error: {}", err);
+        }
+        (report, String::from_utf8_lossy(&output).into_owned())
+    }
+
+    /// """Computes the smallest memory capacity that lets a command trace allocate without failing.
+    ///
+    /// The trace is replayed, with output discarded, against a manager that starts empty and
+    /// grows its memory whenever an INSERT or INSERTD would not fit. The final size of that
+    /// memory is the high-water mark the trace needs under best-fit placement. Memory is only
+    /// grown up to 4 GiB, so a trace that needs more, e.g. one with a size near `usize::MAX`,
+    /// has no answer.
+    ///
+    /// Args:
+    ///     commands (&[String]): The command lines to simulate, in order.
+    ///
+    /// Returns:
+    ///     Option<usize>: The minimum capacity in bytes, or None if some allocation in the trace
+    ///     would need memory beyond 4 GiB.
+    /// """
+    pub fn min_capacity_for(commands: &[String]) -> Option<usize> {
+        let mut memory_manager = MemoryManager::with_capacity(0);
+        memory_manager.growable = true;
+        let report = replay(commands, &mut memory_manager);
+        (report.failed_allocations == 0).then(|| memory_manager.capacity())
+    }
+
+    /// """Replays a command trace against a fresh manager with output discarded.
+    ///
+    /// Args:
+    ///     commands (&[String]): The command lines to replay, in order.
+    ///     memory_manager (&mut MemoryManager): The fresh manager to replay against.
+    ///
+    /// Returns:
+    ///     ProcessReport: A summary of the command outcomes.
+    /// """
+    fn replay(commands: &[String], memory_manager: &mut MemoryManager) -> ProcessReport {
+        let mut report = ProcessReport::default();
+        // Writing to a sink cannot fail and a fresh manager never stops early, so replay always completes.
+        let _ = run_commands(commands.iter().cloned(), memory_manager, &mut io::sink(), &mut report);
+        report
+    }
+
+    /// """Finds the first command in a trace whose allocation fails at a given capacity.
+    ///
+    /// The trace is replayed against a fresh manager of `capacity` bytes, stopping at the
+    /// first INSERT or INSERTD that cannot be satisfied.
+    ///
+    /// Args:
+    ///     commands (&[String]): The command lines to replay, in order.
+    ///     capacity (usize): The capacity of the manager to replay against.
+    ///
+    /// Returns:
+    ///     Option<usize>: The 0-based index of the first failing allocation command, or None if
+    ///     every allocation succeeds.
+    /// """
+    pub fn first_failure_index(commands: &[String], capacity: usize) -> Option<usize> {
+        let mut memory_manager = MemoryManager::with_capacity(capacity);
+        commands
+            .iter()
+            .position(|line| matches!(process_line(line, &mut memory_manager), CommandResult::AllocationFailed(_)))
+    }
+
+    /// """Replays the same command trace under several allocation strategies.
+    ///
+    /// Each strategy gets a fresh default-capacity manager, the trace is replayed with output
+    /// discarded, and the final statistics are collected so placement policies, such as
+    /// `BestFit` against `FirstFit`, can be compared side by side.
+    ///
+    /// Args:
+    ///     commands (&[String]): The command lines to replay, in order.
+    ///     strategies (&[AllocStrategy]): The strategies to compare.
+    ///
+    /// Returns:
+    ///     Vec<(AllocStrategy, MemoryStats)>: Each strategy with the stats at the end of its replay,
+    ///     in the order given.
+    /// """
+    pub fn compare_strategies(commands: &[String], strategies: &[AllocStrategy]) -> Vec<(AllocStrategy, MemoryStats)> {
+        strategies
+            .iter()
+            .map(|&strategy| {
+                let mut memory_manager = MemoryManager::builder().strategy(strategy).build();
+                replay(commands, &mut memory_manager);
+                (strategy, memory_manager.stats())
+            })
+            .collect()
+    }
+
+    /// """Processes a sequence of commands, writing all output to the given sink.
+    ///
+    /// Args:
+    ///     commands (IntoIterator<Item = String>): The command lines to process, in order.
+    ///     memory_manager (MemoryManager): A mutable reference to the memory manager instance.
+    ///     out (&mut dyn Write): The sink all command output is written to.
+    ///
+    /// Returns:
+    ///     io::Result<ProcessReport>: A summary of the command outcomes, or the error returned by the sink.
+    ///     In strict mode, a failed ASSERT also stops processing with an error naming its line number,
+    ///     as does deleting an unknown ID under `UnknownPolicy::Fail` or running past `max_total_operations`.
+    /// """
+    pub fn process_commands_to<I>(
+        commands: I,
+        memory_manager: &mut MemoryManager,
+        out: &mut dyn Write,
+    ) -> io::Result<ProcessReport>
+    where
+        I: IntoIterator<Item = String>,
+    {
+        let mut report = ProcessReport::default();
+        run_commands(commands, memory_manager, out, &mut report)?;
+        Ok(report)
+    }
+
+    /// """Processes a sequence of commands, validating the allocator after every one.
+    ///
+    /// Output is discarded. After each command `MemoryManager::validate` checks the allocator's
+    /// invariants, so the first command that corrupts its state can be pinpointed.
+    ///
+    /// Args:
+    ///     commands (IntoIterator<Item = String>): The command lines to process, in order.
+    ///     memory_manager (MemoryManager): A mutable reference to the memory manager instance.
+    ///
+    /// Returns:
+    ///     Result<ProcessReport, (usize, String)>: A summary of the command outcomes, or the 0-based
+    ///     index of the command after which an invariant was violated and the violation message.
+    ///     A strict-mode failure is reported the same way with its error message.
+    /// """
+    pub fn process_commands_checked<I>(commands: I, memory_manager: &mut MemoryManager) -> Result<ProcessReport, (usize, String)>
+    where
+        I: IntoIterator<Item = String>,
+    {
+        let mut report = ProcessReport::default();
+        let mut processed = 0;
+        let commands = commands.into_iter().inspect(|_| processed += 1);
+        let mut check = |memory_manager: &MemoryManager| memory_manager.validate().map_err(io::Error::other);
+        // Writing to a sink cannot fail, so any error came from a check or strict mode.
+        match run_commands_checked(commands, memory_manager, &mut io::sink(), &mut report, &mut check) {
+            Ok(()) => Ok(report),
+            Err(err) => Err((processed - 1, err.to_string())),
+        }
+    }
+
+    /// """Executes command lines in order, accumulating their outcomes into a report.
+    ///
+    /// Args:
+    ///     commands (IntoIterator<Item = String>): The command lines to process, in order.
+    ///     memory_manager (MemoryManager): A mutable reference to the memory manager instance.
+    ///     out (&mut dyn Write): The sink all command output is written to.
+    ///     report (&mut ProcessReport): The report updated with each command's outcome.
+    ///
+    /// Returns:
+    ///     io::Result<()>: Ok(()) unless writing fails, a strict-mode assertion fails or the operation
+    ///     limit is reached, in which case the error is prefixed with the 1-based line number. The
+    ///     sink is flushed after the last command.
+    /// """
+    fn run_commands<I>(
+        commands: I,
+        memory_manager: &mut MemoryManager,
+        out: &mut dyn Write,
+        report: &mut ProcessReport,
+    ) -> io::Result<()>
+    where
+        I: IntoIterator<Item = String>,
+    {
+        run_commands_checked(commands, memory_manager, out, report, &mut |_| Ok(()))
+    }
+
+    /// """Executes command lines like `run_commands`, running a check after each one.
+    ///
+    /// Args:
+    ///     commands (IntoIterator<Item = String>): The command lines to process, in order.
+    ///     memory_manager (MemoryManager): A mutable reference to the memory manager instance.
+    ///     out (&mut dyn Write): The sink all command output is written to.
+    ///     report (&mut ProcessReport): The report updated with each command's outcome.
+    ///     check (&mut dyn FnMut(&MemoryManager) -> io::Result<()>): Called with the active manager
+    ///         after each command; an error stops processing.
+    ///
+    /// Returns:
+    ///     io::Result<()>: As for `run_commands`, or the first error returned by `check`.
+    /// """
+    fn run_commands_checked<I>(
+        commands: I,
+        memory_manager: &mut MemoryManager,
+        out: &mut dyn Write,
+        report: &mut ProcessReport,
+        check: &mut dyn FnMut(&MemoryManager) -> io::Result<()>,
+    ) -> io::Result<()>
+    where
+        I: IntoIterator<Item = String>,
+    {
+        // Managers forked by FORK that are not currently active, keyed by name.
+        let mut forks: HashMap<String, MemoryManager> = HashMap::new();
+        let mut active = MAIN_FORK.to_string();
+        let outcome = commands.into_iter().enumerate().try_for_each(|(index, line)| {
+            if !line.trim().is_empty() {
+                check_operation_limit(index + 1, memory_manager)?;
+            }
+            writeln!(out, "Processing line: {}", line)?;
+            let tokens: Vec<&str> = line.split_whitespace().collect();
+            let result = match fork_command(&tokens, memory_manager, &mut forks, &mut active) {
+                Some(result) => result,
+                None => process_line(&line, memory_manager),
+            };
+            finish_command(index + 1, &result, memory_manager, out, report)?;
+            check(memory_manager)
+        });
+        // Hand the caller back the manager it passed in, whichever fork ran last, even when
+        // processing stopped early.
+        if let Some(main) = forks.remove(MAIN_FORK) {
+            *memory_manager = main;
+        }
+        outcome?;
+        if report.commands == 0 {
+            writeln!(out, "No commands processed")?;
+        }
+        out.flush()
+    }
+
+    /// """The name under which the manager passed to command processing can be switched back to.
+    /// """
+    const MAIN_FORK: &str = "main";
+
+    /// """Executes FORK and SWITCH, which act on the processing loop rather than on one manager.
+    ///
+    /// `FORK <name>` stores a deep copy of the active manager under `name`, replacing any
+    /// earlier fork of that name. `SWITCH <name>` makes the named fork active, parking the
+    /// current one under its own name; the original manager is named `main`, and `main` cannot
+    /// be a FORK target since that would replace the manager handed back to the caller.
+    ///
+    /// Args:
+    ///     tokens (&[&str]): The command keyword followed by its arguments.
+    ///     memory_manager (&mut MemoryManager): The active manager, swapped out on SWITCH.
+    ///     forks (&mut HashMap<String, MemoryManager>): The inactive managers by name.
+    ///     active (&mut String): The name of the active manager.
+    ///
+    /// Returns:
+    ///     Option<CommandResult>: The outcome, or None if the line is not FORK or SWITCH.
+    /// """
+    fn fork_command(
+        tokens: &[&str],
+        memory_manager: &mut MemoryManager,
+        forks: &mut HashMap<String, MemoryManager>,
+        active: &mut String,
+    ) -> Option<CommandResult> {
+        let keyword = *tokens.first()?;
+        if keyword != "FORK" && keyword != "SWITCH" {
+            return None;
+        }
+        memory_manager.record_operation();
+        let [_, name] = *tokens else {
+            return Some(CommandResult::Error(format!("Invalid {} command", keyword)));
+        };
+        if name == active.as_str() {
+            return Some(match keyword {
+                "FORK" => CommandResult::Error(format!("cannot fork onto the active manager `{}`", name)),
+                _ => CommandResult::Switched(name.to_string()),
+            });
+        }
+        if keyword == "FORK" {
+            if name == MAIN_FORK {
+                return Some(CommandResult::Error(format!("cannot fork onto `{}`", MAIN_FORK)));
+            }
+            forks.insert(name.to_string(), memory_manager.clone());
+            return Some(CommandResult::Forked(name.to_string()));
+        }
+        let Some(fork) = forks.remove(name) else {
+            return Some(CommandResult::Error(format!("Unknown fork `{}`", name)));
+        };
+        let previous = std::mem::replace(memory_manager, fork);
+        forks.insert(std::mem::replace(active, name.to_string()), previous);
+        Some(CommandResult::Switched(name.to_string()))
+    }
+
+    /// """Stops processing before a command once the manager's operation limit is used up.
+    ///
+    /// Args:
+    ///     number (usize): The 1-based position of the command, used in the error message.
+    ///     memory_manager (&MemoryManager): The manager the command would run against.
+    ///
+    /// Returns:
+    ///     io::Result<()>: Ok(()) if the command may run, or an error once `max_total_operations`
+    ///     operations have been processed.
+    /// """
+    fn check_operation_limit(number: usize, memory_manager: &MemoryManager) -> io::Result<()> {
+        if memory_manager.operations_remaining() == Some(0) {
+            let message = format!(
+                "line {}: operation limit reached after {} operations",
+                number,
+                memory_manager.operation_count()
+            );
+            return Err(io::Error::other(message));
+        }
+        Ok(())
+    }
+
+    /// """Records a command's outcome and writes it, enforcing strict-mode assertions.
+    ///
+    /// Args:
+    ///     number (usize): The 1-based position of the command, used in error messages.
+    ///     result (&CommandResult): The outcome of the command.
+    ///     memory_manager (MemoryManager): The manager the command ran against; it is compacted
+    ///         here, with a note written, when its auto-compaction settings call for it.
+    ///     out (&mut dyn Write): The sink the outcome is written to.
+    ///     report (&mut ProcessReport): The report updated with the outcome.
+    ///
+    /// Returns:
+    ///     io::Result<()>: Ok(()) unless writing fails, a strict-mode assertion fails, or DELETE names
+    ///     an unknown ID under `UnknownPolicy::Fail`, in which case the error is prefixed with the
+    ///     command number.
+    /// """
+    fn finish_command(
+        number: usize,
+        result: &CommandResult,
+        memory_manager: &mut MemoryManager,
+        out: &mut dyn Write,
+        report: &mut ProcessReport,
+    ) -> io::Result<()> {
+        report.record(result);
+        if let CommandResult::AssertionFailed(message) = result {
+            if memory_manager.is_strict() {
+                let message = format!("line {}: {}", number, message);
+                return Err(io::Error::new(io::ErrorKind::InvalidData, message));
+            }
+        }
+        if let CommandResult::DeleteFailed(err @ AllocError::UnknownId(_)) = result {
+            if memory_manager.unknown_policy() == UnknownPolicy::Fail {
+                let message = format!("line {}: {}", number, err);
+                return Err(io::Error::new(io::ErrorKind::InvalidData, message));
+            }
+        }
+        write_result(result, memory_manager, out)
+            .map_err(|err| io::Error::new(err.kind(), format!("line {}: {}", number, err)))?;
+        if *result != CommandResult::Blank && memory_manager.auto_compact() {
+            writeln!(out, "Auto-compacted memory after line {}", number)?;
+        }
+        Ok(())
+    }
+
+    /// """Executes a single command line against the memory manager.
+    ///
+    /// Nothing is printed; the outcome is returned so callers can inspect it directly or
+    /// format it with the same wording used for command files.
+    ///
+    /// Args:
+    ///     line (&str): The command line to execute.
+    ///     memory_manager (MemoryManager): A mutable reference to the memory manager instance.
+    ///
+    /// Returns:
+    ///     CommandResult: The outcome of the command.
+    /// """
+    pub fn process_line(line: &str, memory_manager: &mut MemoryManager) -> CommandResult {
+        let tokens = command_tokens(line);
+        let tokens: Vec<&str> = tokens.iter().map(String::as_str).collect();
+        execute(&tokens, memory_manager)
+    }
+
+    /// """Splits a text command line into its keyword and arguments.
+    ///
+    /// Arguments are whitespace-separated, except that INSERTD takes everything after the
+    /// keyword as one (possibly quoted) data argument, which is unquoted here. If that data
+    /// cannot be parsed, only the keyword is returned.
+    ///
+    /// Args:
+    ///     line (&str): The command line to split.
+    ///
+    /// Returns:
+    ///     Vec<String>: The keyword followed by its arguments, or an empty list for a blank line.
+    /// """
+    fn command_tokens(line: &str) -> Vec<String> {
+        let mut tokens: Vec<String> = line.split_whitespace().map(str::to_string).collect();
+        if tokens.first().is_some_and(|keyword| keyword == "INSERTD") {
+            // Everything after the command keyword is the (possibly quoted) data.
+            let rest = line.trim_start()["INSERTD".len()..].trim();
+            tokens.truncate(1);
+            tokens.extend(parse_data(rest));
+        }
+        tokens
+    }
+
+    /// """Executes a command that has already been split into its keyword and arguments.
+    ///
+    /// This is the logic shared by the text and binary command formats. For INSERTD the
+    /// single argument is the data itself, with any quoting already removed.
+    ///
+    /// Args:
+    ///     tokens (&[&str]): The command keyword followed by its arguments.
+    ///     memory_manager (MemoryManager): A mutable reference to the memory manager instance.
+    ///
+    /// Returns:
+    ///     CommandResult: The outcome of the command, or `Blank` if `tokens` is empty.
+    /// """
+    fn execute(tokens: &[&str], memory_manager: &mut MemoryManager) -> CommandResult {
+        if tokens.is_empty() {
+            return CommandResult::Blank;
+        }
+        memory_manager.record_operation();
+        match tokens[0] {
+            "INSERT" => {
+                if tokens.len() < 2 {
+                    return CommandResult::Error("Invalid INSERT command".to_string());
+                }
+                // Parse the size and use the third token, if any, as data (as bytes).
+                let size = match parse_field("INSERT", "size", tokens[1]) {
+                    Ok(size) => size,
+                    Err(message) => return CommandResult::Error(message),
+                };
+                let result = match tokens.get(2) {
+                    Some(data) => memory_manager.insert(size, data.as_bytes()),
+                    None => memory_manager.reserve(size),
+                };
+                insert_result(result, memory_manager)
+            }
+            "INSERTD" => {
+                if tokens.len() != 2 {
+                    return CommandResult::Error("Invalid INSERTD command".to_string());
+                }
+                insert_result(memory_manager.insert_data(tokens[1].as_bytes()), memory_manager)
+            }
+            "DELETE" => {
+                if tokens.len() < 2 {
+                    return CommandResult::Error("Invalid DELETE command".to_string());
+                }
+                let id = match parse_field("DELETE", "ID", tokens[1]) {
+                    Ok(id) => id,
+                    Err(message) => return CommandResult::Error(message),
+                };
+                // Capture the block's placement before it is freed so it can be reported.
+                let Some((start, size)) = memory_manager.find_with_addr(id).map(|(start, data)| (start, data.len())) else {
+                    return CommandResult::DeleteFailed(AllocError::UnknownId(id));
+                };
+                match memory_manager.delete(id) {
+                    Ok(()) => CommandResult::Deleted { id, start, size },
+                    Err(err) => CommandResult::DeleteFailed(err),
+                }
+            }
+            "FIND" => {
+                if tokens.len() < 2 {
+                    return CommandResult::Error("Invalid FIND command".to_string());
+                }
+                let id = match parse_field("FIND", "ID", tokens[1]) {
+                    Ok(id) => id,
+                    Err(message) => return CommandResult::Error(message),
+                };
+                match memory_manager.find_with_addr(id) {
+                    Some((start, data)) => CommandResult::Found {
+                        id,
+                        start,
+                        data: data.to_vec(),
+                    },
+                    None => CommandResult::NotFound(id),
+                }
+            }
+            "READ" => {
+                if tokens.len() != 2 {
+                    return CommandResult::Error("Invalid READ command format".to_string());
+                }
+                let id = match parse_field("READ", "ID", tokens[1]) {
+                    Ok(id) => id,
+                    Err(message) => return CommandResult::Error(message),
+                };
+                match memory_manager.find(id) {
+                    Some(data) => CommandResult::Read { id, data: data.to_vec() },
+                    None => CommandResult::Error(AllocError::UnknownId(id).to_string()),
+                }
+            }
+            "UPDATE" => {
+                if tokens.len() < 3 {
+                    return CommandResult::Error("Invalid UPDATE command".to_string());
+                }
+                let id = match parse_field("UPDATE", "ID", tokens[1]) {
+                    Ok(id) => id,
+                    Err(message) => return CommandResult::Error(message),
+                };
+                let new_data = tokens[2].as_bytes();
+                match memory_manager.update(id, new_data) {
+                    Ok(()) => CommandResult::Updated { id, data: new_data.to_vec() },
+                    Err(err) => CommandResult::Error(err.to_string()),
+                }
+            }
+            "DUMP" => CommandResult::Dumped,
+            "STATS" => CommandResult::Stats(memory_manager.stats()),
+            "SLIVERS" => {
+                if tokens.len() < 2 {
+                    return CommandResult::Error("Invalid SLIVERS command".to_string());
+                }
+                let below = match parse_field("SLIVERS", "size", tokens[1]) {
+                    Ok(below) => below,
+                    Err(message) => return CommandResult::Error(message),
+                };
+                CommandResult::Slivers {
+                    below,
+                    slivers: memory_manager.slivers(below),
+                }
+            }
+            "RADIX" => {
+                let radix = match tokens.get(1).copied() {
+                    Some("hex") => AddressRadix::Hex,
+                    Some("dec") => AddressRadix::Dec,
+                    _ => return CommandResult::Error("Invalid RADIX command".to_string()),
+                };
+                memory_manager.set_radix(radix);
+                CommandResult::RadixSet(radix)
+            }
+            "DEFRAG" => {
+                if tokens.get(1) != Some(&"SMART") {
+                    return CommandResult::Error("Invalid DEFRAG command".to_string());
+                }
+                CommandResult::Defragmented(memory_manager.smart_defrag())
+            }
+            "PROTECT" => {
+                if tokens.len() < 2 {
+                    return CommandResult::Error("Invalid PROTECT command".to_string());
+                }
+                let id = match parse_field("PROTECT", "ID", tokens[1]) {
+                    Ok(id) => id,
+                    Err(message) => return CommandResult::Error(message),
+                };
+                match memory_manager.protect(id) {
+                    Ok(()) => CommandResult::Protected(id),
+                    Err(err) => CommandResult::Error(err.to_string()),
+                }
+            }
+            "ASSERT" => {
+                if tokens.len() < 3 {
+                    return CommandResult::Error("Invalid ASSERT command".to_string());
+                }
+                if tokens[1] == "DATA" {
+                    return assert_data(tokens, memory_manager);
+                }
+                let expected = match parse_field("ASSERT", "count", tokens[2]) {
+                    Ok(expected) => expected,
+                    Err(message) => return CommandResult::Error(message),
+                };
+                let stats = memory_manager.stats();
+                let actual = match tokens[1] {
+                    "FREE" => stats.free_block_count,
+                    "ALLOCATED" => stats.allocation_count,
+                    "USED" => stats.used_bytes,
+                    _ => return CommandResult::Error(format!("Unknown ASSERT target `{}`", tokens[1])),
+                };
+                if actual == expected {
+                    CommandResult::AssertionPassed(format!("{} is {}", tokens[1], expected))
+                } else {
+                    CommandResult::AssertionFailed(format!(
+                        "assertion failed: expected {} {}, found {}",
+                        tokens[1], expected, actual
+                    ))
+                }
+            }
+            "DEDUP" => {
+                let (remap, bytes) = memory_manager.dedup_with_remap();
+                CommandResult::Deduplicated {
+                    freed: remap.len(),
+                    bytes,
+                }
+            }
+            "FLUSH" => CommandResult::Flushed,
+            "CONFIG" => CommandResult::Config(memory_manager.config()),
+            "LOADBLOB" => {
+                if tokens.len() != 2 {
+                    return CommandResult::Error("Invalid LOADBLOB command".to_string());
+                }
+                match memory_manager.insert_file(Path::new(tokens[1])) {
+                    Ok(result) => insert_result(result, memory_manager),
+                    Err(err) => CommandResult::Error(format!("cannot read `{}`: {}", tokens[1], err)),
+                }
+            }
+            "SAVEBLOB" => {
+                if tokens.len() != 3 {
+                    return CommandResult::Error("Invalid SAVEBLOB command".to_string());
+                }
+                let id = match parse_field("SAVEBLOB", "id", tokens[1]) {
+                    Ok(id) => id,
+                    Err(message) => return CommandResult::Error(message),
+                };
+                match memory_manager.write_file(id, Path::new(tokens[2])) {
+                    Ok(()) => CommandResult::Saved { id, path: tokens[2].to_string() },
+                    Err(err) => CommandResult::Error(format!("cannot save ID {} to `{}`: {}", id, tokens[2], err)),
+                }
+            }
+            "BENCH" => {
+                if tokens.len() != 3 {
+                    return CommandResult::Error("Invalid BENCH command".to_string());
+                }
+                let (size, count) = match (parse_field("BENCH", "size", tokens[1]), parse_field("BENCH", "count", tokens[2])) {
+                    (Ok(size), Ok(count)) => (size, count),
+                    // Report every malformed field, not just the first.
+                    (size, count) => {
+                        let messages: Vec<String> = [size, count].into_iter().filter_map(Result::err).collect();
+                        return CommandResult::Error(messages.join("; "));
                     }
+                };
+                let elapsed = memory_manager.bench_alloc(size, count);
+                CommandResult::Benchmarked { size, count, elapsed }
+            }
+            "LOGFMT" => {
+                let format = match tokens.get(1).copied() {
+                    Some("human") => LogFormat::Human,
+                    Some("csv") => LogFormat::Csv,
+                    _ => return CommandResult::Error("Invalid LOGFMT command".to_string()),
+                };
+                memory_manager.set_log_format(format);
+                CommandResult::LogFormatSet(format)
+            }
+            _ => CommandResult::Error(format!("Unknown command `{}`", tokens[0])),
+        }
+    }
+
+    /// """Executes `ASSERT DATA <id> <bytes>`, checking an allocation's exact contents.
+    ///
+    /// Args:
+    ///     tokens (&[&str]): The ASSERT command split into its keyword and arguments.
+    ///     memory_manager (&MemoryManager): The manager holding the allocation.
+    ///
+    /// Returns:
+    ///     CommandResult: `AssertionPassed` or `AssertionFailed`, or `Error` if the command is malformed.
+    /// """
+    fn assert_data(tokens: &[&str], memory_manager: &MemoryManager) -> CommandResult {
+        if tokens.len() != 4 {
+            return CommandResult::Error("Invalid ASSERT DATA command".to_string());
+        }
+        let id = match parse_field("ASSERT DATA", "ID", tokens[2]) {
+            Ok(id) => id,
+            Err(message) => return CommandResult::Error(message),
+        };
+        let expected = tokens[3].as_bytes();
+        if memory_manager.verify(id, expected) {
+            return CommandResult::AssertionPassed(format!("DATA {} is {:?}", id, expected));
+        }
+        let found = match memory_manager.find(id) {
+            Some(actual) => format!("{:?}", actual),
+            None => "nothing".to_string(),
+        };
+        CommandResult::AssertionFailed(format!("assertion failed: expected DATA {} {:?}, found {}", id, expected, found))
+    }
+
+    /// """Parses a numeric command argument, describing the failure if it is not one.
+    ///
+    /// Args:
+    ///     command (&str): The command keyword, used in the error message.
+    ///     field (&str): The name of the argument, used in the error message.
+    ///     token (&str): The argument text to parse.
+    ///
+    /// Returns:
+    ///     Result<usize, String>: The parsed value, or a message naming the command, the field,
+    ///     the offending text and the reason it was rejected.
+    /// """
+    fn parse_field(command: &str, field: &str, token: &str) -> Result<usize, String> {
+        token
+            .parse::<usize>()
+            .map_err(|err| format!("{} {} '{}' is not a valid non-negative integer ({})", command, field, token, err))
+    }
+
+    /// """Converts the result of an insert into a command result.
+    ///
+    /// Args:
+    ///     result (Result<usize, AllocError>): The result returned by the insert.
+    ///     memory_manager (&MemoryManager): The manager the insert ran against.
+    ///
+    /// Returns:
+    ///     CommandResult: `Allocated` with the new block's placement on success, or
+    ///     `AllocationFailed` with the error.
+    /// """
+    fn insert_result(result: Result<usize, AllocError>, memory_manager: &MemoryManager) -> CommandResult {
+        match result {
+            Ok(id) => {
+                let (start, data) = memory_manager.find_with_addr(id).expect("a new allocation is always present");
+                CommandResult::Allocated {
+                    id,
+                    start,
+                    size: data.len(),
                 }
             }
+            Err(err) => CommandResult::AllocationFailed(err),
         }
-        Ok(())
+    }
+
+    /// """Writes the textual form of a command result, honouring the manager's log format.
+    ///
+    /// Args:
+    ///     result (&CommandResult): The outcome to format.
+    ///     memory_manager (&MemoryManager): The manager the command ran against, used for
+    ///         address formatting and dumps.
+    ///     out (&mut dyn Write): The sink the text is written to.
+    ///
+    /// Returns:
+    ///     io::Result<()>: Ok(()) unless writing to the sink fails.
+    /// """
+    pub fn write_result(result: &CommandResult, memory_manager: &MemoryManager, out: &mut dyn Write) -> io::Result<()> {
+        match result {
+            CommandResult::Blank => Ok(()),
+            CommandResult::Allocated { id, start, size } => match memory_manager.log_format() {
+                LogFormat::Human => writeln!(out, "Allocated ID: {}", id),
+                LogFormat::Csv => writeln!(out, "insert,{},{},{},{}", id, start, size, memory_manager.operation_count()),
+            },
+            CommandResult::AllocationFailed(err) => writeln!(out, "Memory allocation failed: {}", err),
+            CommandResult::Deleted { id, start, size } => match memory_manager.log_format() {
+                LogFormat::Human => writeln!(out, "Deleted ID: {}", id),
+                LogFormat::Csv => writeln!(out, "delete,{},{},{},{}", id, start, size, memory_manager.operation_count()),
+            },
+            CommandResult::Found { id, start, data } => {
+                let start = memory_manager.format_address(*start);
+                writeln!(out, "Data at {} (Start: {}): {:?}", id, start, data)
+            }
+            CommandResult::NotFound(id) => writeln!(out, "Nothing at {}", id),
+            CommandResult::Read { id, data } => writeln!(out, "Data at ID {}: {:?}", id, data),
+            CommandResult::Updated { id, data } => writeln!(out, "Updated ID: {} with new data {:?}", id, data),
+            CommandResult::Dumped => memory_manager.dump_to(out),
+            CommandResult::Stats(stats) => writeln!(out, "{}", stats),
+            CommandResult::Slivers { below, slivers } => {
+                for (start, size) in slivers {
+                    writeln!(out, "SLIVER: Start: {}, Size: {}", memory_manager.format_address(*start), size)?;
+                }
+                let total: usize = slivers.iter().map(|(_, size)| size).sum();
+                writeln!(out, "Slivers below {}: {} totaling {} bytes", below, slivers.len(), total)
+            }
+            CommandResult::RadixSet(radix) => {
+                let name = match radix {
+                    AddressRadix::Hex => "hex",
+                    AddressRadix::Dec => "dec",
+                };
+                writeln!(out, "Address radix set to {}", name)
+            }
+            CommandResult::Defragmented(moved) => writeln!(out, "Defragmented: moved {} blocks", moved),
+            CommandResult::Protected(id) => writeln!(out, "Protected ID: {}", id),
+            CommandResult::Saved { id, path } => writeln!(out, "Saved ID: {} to {}", id, path),
+            CommandResult::Benchmarked { size, count, elapsed } => {
+                let per_pair = if *count == 0 { 0.0 } else { elapsed.as_nanos() as f64 / *count as f64 };
+                writeln!(
+                    out,
+                    "Benchmark: {} allocations of {} bytes in {:?} ({:.1} ns per insert/delete)",
+                    count, size, elapsed, per_pair
+                )
+            }
+            CommandResult::Config(config) => writeln!(out, "{}", config),
+            CommandResult::Forked(name) => writeln!(out, "Forked as {}", name),
+            CommandResult::Switched(name) => writeln!(out, "Switched to {}", name),
+            CommandResult::LogFormatSet(format) => {
+                let name = match format {
+                    LogFormat::Human => "human",
+                    LogFormat::Csv => "csv",
+                };
+                writeln!(out, "Log format set to {}", name)
+            }
+            CommandResult::Deduplicated { freed, bytes } => {
+                writeln!(out, "Deduplicated: freed {} allocations, reclaimed {} bytes", freed, bytes)
+            }
+            CommandResult::Flushed => out.flush(),
+            CommandResult::AssertionPassed(message) => writeln!(out, "Assertion passed: {}", message),
+            CommandResult::DeleteFailed(err) => writeln!(out, "Error: {}", err),
+            CommandResult::AssertionFailed(message) => writeln!(out, "Error: {}", message),
+            CommandResult::Error(message) => writeln!(out, "Error: {}", message),
+        }
+    }
+
+    /// """Command keywords indexed by their opcode in the binary command format.
+    ///
+    /// New commands must be appended so that existing binary files keep decoding.
+    /// """
+    const OPCODES: [&str; 22] = [
+        "INSERT", "INSERTD", "DELETE", "FIND", "READ", "UPDATE", "DUMP", "STATS", "SLIVERS", "ASSERT", "RADIX",
+        "DEFRAG", "PROTECT", "DEDUP", "FLUSH", "LOGFMT", "CONFIG", "LOADBLOB", "SAVEBLOB", "BENCH", "FORK", "SWITCH",
+    ];
+
+    /// """Processes a binary command file, printing all output to stdout.
+    ///
+    /// Binary files (conventionally `.cmmdb`) avoid text parsing for very large traces. Each
+    /// command is an opcode byte (see `OPCODES`), a field-count byte, and then each field as a
+    /// little-endian `u32` byte length followed by that many UTF-8 bytes. Commands run through
+    /// the same logic as text commands, including FORK and SWITCH; use `convert_to_binary` to
+    /// produce such a file.
+    ///
+    /// Args:
+    ///     file_path (&str): The path to the binary command file.
+    ///     memory_manager (MemoryManager): A mutable reference to the memory manager instance.
+    ///
+    /// Returns:
+    ///     io::Result<ProcessReport>: A summary of the command outcomes, or an error if the file
+    ///     cannot be read or is malformed, writing to stdout fails, or processing runs past
+    ///     `max_total_operations`.
+    /// """
+    pub fn process_binary_file(file_path: &str, memory_manager: &mut MemoryManager) -> io::Result<ProcessReport> {
+        let mut reader = io::BufReader::new(File::open(file_path)?);
+        let stdout = io::stdout();
+        let mut out = stdout.lock();
+        let mut report = ProcessReport::default();
+        let mut forks: HashMap<String, MemoryManager> = HashMap::new();
+        let mut active = MAIN_FORK.to_string();
+        let mut number = 0;
+        let mut run = || -> io::Result<()> {
+            while let Some(tokens) = read_binary_command(&mut reader)? {
+                number += 1;
+                check_operation_limit(number, memory_manager)?;
+                let tokens: Vec<&str> = tokens.iter().map(String::as_str).collect();
+                writeln!(out, "Processing line: {}", tokens.join(" "))?;
+                let result = match fork_command(&tokens, memory_manager, &mut forks, &mut active) {
+                    Some(result) => result,
+                    None => execute(&tokens, memory_manager),
+                };
+                finish_command(number, &result, memory_manager, &mut out, &mut report)?;
+            }
+            out.flush()
+        };
+        let outcome = run();
+        // As for text commands, hand back the caller's manager even when processing stopped early.
+        if let Some(main) = forks.remove(MAIN_FORK) {
+            *memory_manager = main;
+        }
+        outcome.map(|()| report)
+    }
+
+    /// """Converts a text command file into the binary command format.
+    ///
+    /// Blank lines are dropped. INSERTD data is stored unquoted, exactly as it would be inserted.
+    ///
+    /// Args:
+    ///     text_path (&str): The path to the text command file to read.
+    ///     binary_path (&str): The path of the binary file to create or overwrite.
+    ///
+    /// Returns:
+    ///     io::Result<usize>: The number of commands written, or an error if a file cannot be
+    ///     accessed or a line uses an unknown command or has more than 255 fields.
+    /// """
+    pub fn convert_to_binary(text_path: &str, binary_path: &str) -> io::Result<usize> {
+        let mut out = io::BufWriter::new(File::create(binary_path)?);
+        let mut written = 0;
+        for (index, line) in read_lines(text_path)?.enumerate() {
+            let tokens = command_tokens(&line?);
+            let Some((keyword, fields)) = tokens.split_first() else {
+                continue;
+            };
+            let invalid = |message: String| io::Error::new(io::ErrorKind::InvalidData, format!("line {}: {}", index + 1, message));
+            let opcode = OPCODES
+                .iter()
+                .position(|known| known == keyword)
+                .ok_or_else(|| invalid(format!("Unknown command `{}`", keyword)))?;
+            let count = u8::try_from(fields.len()).map_err(|_| invalid("too many fields".to_string()))?;
+            out.write_all(&[opcode as u8, count])?;
+            for field in fields {
+                let len = u32::try_from(field.len()).map_err(|_| invalid("field too long".to_string()))?;
+                out.write_all(&len.to_le_bytes())?;
+                out.write_all(field.as_bytes())?;
+            }
+            written += 1;
+        }
+        out.flush()?;
+        Ok(written)
+    }
+
+    /// """Decodes the next command from a binary command stream.
+    ///
+    /// Args:
+    ///     reader (&mut dyn Read): The stream positioned at the start of a command.
+    ///
+    /// Returns:
+    ///     io::Result<Option<Vec<String>>>: The command keyword followed by its fields, None at the
+    ///     end of the stream, or an error if the command is truncated, has an unknown opcode or a
+    ///     field is not valid UTF-8.
+    /// """
+    fn read_binary_command(reader: &mut dyn Read) -> io::Result<Option<Vec<String>>> {
+        let mut header = [0u8; 2];
+        match reader.read(&mut header[..1])? {
+            0 => return Ok(None),
+            _ => reader.read_exact(&mut header[1..])?,
+        }
+        let [opcode, count] = header;
+        let keyword = OPCODES.get(opcode as usize).ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidData, format!("unknown opcode {}", opcode))
+        })?;
+
+        let mut tokens = vec![keyword.to_string()];
+        for _ in 0..count {
+            let mut len = [0u8; 4];
+            reader.read_exact(&mut len)?;
+            let len = u32::from_le_bytes(len);
+            // Read only the bytes actually present, so a corrupt length cannot force a huge allocation.
+            let mut field = Vec::new();
+            if (&mut *reader).take(len.into()).read_to_end(&mut field)? != len as usize {
+                return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "truncated command field"));
+            }
+            let field = String::from_utf8(field).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+            tokens.push(field);
+        }
+        Ok(Some(tokens))
+    }
+
+    /// """Parses a data argument that is either a bare token or a double-quoted string.
+    ///
+    /// Quoted strings may contain whitespace and use `\"` and `\\` as escapes. Bare data
+    /// must be a single whitespace-separated token.
+    ///
+    /// Args:
+    ///     text (&str): The argument text following the command keyword.
+    ///
+    /// Returns:
+    ///     Option<String>: The parsed data, or None if the argument is missing, unterminated,
+    ///     or followed by trailing text, e.g. a second bare token.
+    /// """
+    fn parse_data(text: &str) -> Option<String> {
+        let Some(quoted) = text.strip_prefix('"') else {
+            let mut words = text.split_whitespace();
+            return match (words.next(), words.next()) {
+                (Some(word), None) => Some(word.to_string()),
+                _ => None,
+            };
+        };
+
+        let mut data = String::new();
+        let mut chars = quoted.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => data.push(chars.next()?),
+                '"' => return chars.as_str().trim().is_empty().then_some(data),
+                _ => data.push(c),
+            }
+        }
+        // Reached the end of the line without a closing quote.
+        None
     }
 
     /// """Reads lines from a file.
     ///
+    /// With the `gzip` feature enabled, files with a `.gz` extension are transparently
+    /// decompressed while reading.
+    ///
     /// Args:
     ///     filename: A value that can be referenced as a file path.
     ///
     /// Returns:
-    ///     io::Result<io::Lines<io::BufReader<File>>>: An iterator over the lines in the file or an I/O error.
+    ///     io::Result<io::Lines<Box<dyn BufRead>>>: An iterator over the lines in the file or an I/O error.
     /// """
-    fn read_lines<P>(filename: P) -> io::Result<io::Lines<io::BufReader<File>>>
+    fn read_lines<P>(filename: P) -> io::Result<io::Lines<Box<dyn BufRead>>>
     where
         P: AsRef<Path>,
     {
-        let file = File::open(filename)?;
-        Ok(io::BufReader::new(file).lines())
+        let path = filename.as_ref();
+        let file = File::open(path)?;
+        #[cfg(feature = "gzip")]
+        if path.extension().is_some_and(|ext| ext == "gz") {
+            let reader: Box<dyn BufRead> = Box::new(io::BufReader::new(flate2::read::GzDecoder::new(file)));
+            return Ok(reader.lines());
+        }
+        let reader: Box<dyn BufRead> = Box::new(io::BufReader::new(file));
+        Ok(reader.lines())
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn lines(commands: &[&str]) -> Vec<String> {
+            commands.iter().map(|line| line.to_string()).collect()
+        }
+
+        #[test]
+        fn fork_rejects_main_as_a_target() {
+            let mut mm = MemoryManager::with_capacity(64);
+            let commands = lines(&["INSERT 8", "FORK other", "SWITCH other", "FORK main", "INSERT 8", "SWITCH main"]);
+            let (_, out) = process_commands_captured(commands, &mut mm);
+
+            assert!(out.contains("Error: cannot fork onto `main`"));
+            // The caller's manager is handed back untouched by the fork's second insert.
+            assert_eq!(mm.stats().allocation_count, 1);
+        }
+
+        /// """Returns a path in the system temp directory unique to this process and `name`.
+        /// """
+        fn temp_path(name: &str) -> std::path::PathBuf {
+            std::env::temp_dir().join(format!("memory_manager_{}_{}", std::process::id(), name))
+        }
+
+        #[test]
+        fn binary_files_support_fork_and_switch() {
+            let text = temp_path("fork.txt");
+            let binary = temp_path("fork.cmmdb");
+            std::fs::write(&text, "INSERT 8\nFORK other\nSWITCH other\nINSERT 8\nSWITCH main\n").unwrap();
+
+            assert_eq!(convert_to_binary(text.to_str().unwrap(), binary.to_str().unwrap()).unwrap(), 5);
+            let mut mm = MemoryManager::with_capacity(64);
+            let report = process_binary_file(binary.to_str().unwrap(), &mut mm).unwrap();
+            std::fs::remove_file(&text).unwrap();
+            std::fs::remove_file(&binary).unwrap();
+
+            assert_eq!(report.commands, 5);
+            assert_eq!(report.errors, 0);
+            assert_eq!(mm.stats().allocation_count, 1);
+        }
+
+        #[test]
+        fn truncated_binary_fields_are_rejected() {
+            // INSERT with one field claiming 4 GiB of data but holding only two bytes.
+            let mut bytes: &[u8] = &[0, 1, 0xff, 0xff, 0xff, 0xff, b'1', b'6'];
+            let err = read_binary_command(&mut bytes).unwrap_err();
+
+            assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+        }
+
+        #[test]
+        fn binary_files_honour_the_operation_limit() {
+            let text = temp_path("limit.txt");
+            let binary = temp_path("limit.cmmdb");
+            std::fs::write(&text, "INSERT 8\nINSERT 8\nINSERT 8\n").unwrap();
+
+            convert_to_binary(text.to_str().unwrap(), binary.to_str().unwrap()).unwrap();
+            let mut mm = MemoryManager::builder().capacity(64).max_total_operations(2).build();
+            let err = process_binary_file(binary.to_str().unwrap(), &mut mm).unwrap_err();
+            std::fs::remove_file(&text).unwrap();
+            std::fs::remove_file(&binary).unwrap();
+
+            assert_eq!(err.to_string(), "line 3: operation limit reached after 2 operations");
+            assert_eq!(mm.stats().allocation_count, 2);
+        }
+
+        #[cfg(feature = "gzip")]
+        #[test]
+        fn gzipped_command_files_are_decompressed() {
+            use flate2::write::GzEncoder;
+
+            let path = temp_path("commands.txt.gz");
+            let mut encoder = GzEncoder::new(File::create(&path).unwrap(), flate2::Compression::default());
+            encoder.write_all(b"INSERT 8\nINSERT 4\nDELETE 0\n").unwrap();
+            encoder.finish().unwrap();
+
+            let mut mm = MemoryManager::with_capacity(64);
+            let report = process_file(path.to_str().unwrap(), &mut mm).unwrap();
+            std::fs::remove_file(&path).unwrap();
+
+            assert_eq!(report.commands, 3);
+            assert_eq!(report.allocations, 2);
+            assert_eq!(report.deletions, 1);
+            assert_eq!(mm.stats().allocation_count, 1);
+        }
+
+        #[test]
+        fn insert_without_data_reserves_the_size() {
+            let mut mm = MemoryManager::with_capacity(64);
+            let result = process_line("INSERT 8", &mut mm);
+
+            assert_eq!(result, CommandResult::Allocated { id: 0, start: 0, size: 8 });
+            assert_eq!(mm.find(0).unwrap().len(), 8);
+        }
+
+        #[test]
+        fn insert_with_data_stores_it_at_the_start_of_the_block() {
+            let mut mm = MemoryManager::with_capacity(64);
+            let result = process_line("INSERT 8 abc", &mut mm);
+
+            assert_eq!(result, CommandResult::Allocated { id: 0, start: 0, size: 8 });
+            assert!(mm.find(0).unwrap().starts_with(b"abc"));
+        }
+
+        #[test]
+        fn malformed_sizes_and_ids_are_reported() {
+            let mut mm = MemoryManager::with_capacity(64);
+            let error = |line: &str, mm: &mut MemoryManager| match process_line(line, mm) {
+                CommandResult::Error(message) => message,
+                other => panic!("`{}` gave {:?}", line, other),
+            };
+
+            assert!(error("INSERT -4", &mut mm).starts_with("INSERT size '-4' is not a valid non-negative integer"));
+            assert!(error("DELETE x", &mut mm).starts_with("DELETE ID 'x' is not a valid non-negative integer"));
+            assert!(error("UPDATE 1.5 abc", &mut mm).starts_with("UPDATE ID '1.5' is not a valid non-negative integer"));
+            assert!(error("READ id", &mut mm).starts_with("READ ID 'id' is not a valid non-negative integer"));
+            let both = error("BENCH x -1", &mut mm);
+            assert!(both.starts_with("BENCH size 'x' is not a valid non-negative integer"));
+            assert!(both.contains("; BENCH count '-1' is not a valid non-negative integer"));
+            assert!(mm.is_pristine());
+        }
+
+        #[test]
+        fn double_free_reports_unknown_id() {
+            let mut mm = MemoryManager::with_capacity(64);
+            process_line("INSERT 8", &mut mm);
+            process_line("DELETE 0", &mut mm);
+            assert_eq!(process_line("DELETE 0", &mut mm), CommandResult::DeleteFailed(AllocError::UnknownId(0)));
+
+            mm.set_unknown_policy(UnknownPolicy::Fail);
+            let mut report = ProcessReport::default();
+            let err = run_commands(lines(&["INSERT 8", "DELETE 1", "DELETE 1", "INSERT 8"]), &mut mm, &mut io::sink(), &mut report)
+                .unwrap_err();
+            assert!(err.to_string().starts_with("line 3: "));
+            assert_eq!(report.commands, 3);
+        }
+
+        #[test]
+        fn empty_files_give_an_all_zero_report() {
+            let path = temp_path("empty.txt");
+            std::fs::write(&path, "").unwrap();
+
+            let mut mm = MemoryManager::with_capacity(64);
+            let report = process_file(path.to_str().unwrap(), &mut mm).unwrap();
+            std::fs::remove_file(&path).unwrap();
+
+            assert_eq!(report, ProcessReport::default());
+            assert!(mm.is_pristine());
+        }
+
+        #[test]
+        fn insertd_rejects_extra_bare_tokens() {
+            let mut mm = MemoryManager::with_capacity(64);
+
+            assert_eq!(process_line("INSERTD hello world", &mut mm), CommandResult::Error("Invalid INSERTD command".to_string()));
+            assert_eq!(process_line("INSERTD \"hello world\"", &mut mm), CommandResult::Allocated { id: 0, start: 0, size: 11 });
+            assert_eq!(process_line("INSERTD hello", &mut mm), CommandResult::Allocated { id: 1, start: 11, size: 5 });
+        }
+
+        #[test]
+        fn min_capacity_for_rejects_sizes_that_cannot_be_planned() {
+            assert_eq!(min_capacity_for(&lines(&["INSERT 16", "INSERT 8", "DELETE 0", "INSERT 12"])), Some(24));
+            // The free tail 12..24 is absorbed, so growing only adds the 8-byte shortfall.
+            assert_eq!(min_capacity_for(&lines(&["INSERT 16", "INSERT 8", "DELETE 0", "INSERT 12", "DELETE 1", "INSERT 20"])), Some(32));
+            assert_eq!(min_capacity_for(&lines(&["INSERT 8", "INSERT 18446744073709551615"])), None);
+        }
+
+        #[test]
+        fn compare_strategies_separates_best_fit_and_first_fit() {
+            // Frees leave a 32-byte hole at 0 and a 16-byte hole at 40; the final INSERT picks one.
+            let trace = lines(&["INSERT 32", "INSERT 8", "INSERT 16", "INSERT 8", "DELETE 0", "DELETE 2", "INSERT 16"]);
+            let compared = compare_strategies(&trace, &[AllocStrategy::BestFit, AllocStrategy::FirstFit]);
+
+            assert_eq!(compared[0].0, AllocStrategy::BestFit);
+            assert_eq!(compared[0].1.free_block_count, 2);
+            assert_eq!(compared[1].0, AllocStrategy::FirstFit);
+            assert_eq!(compared[1].1.free_block_count, 3);
+        }
     }
 }
 
@@ -0,0 +1,106 @@
+use crate::{AllocError, MemoryManager, MemoryStats};
+use std::sync::{Mutex, MutexGuard};
+
+/// """A thread-safe wrapper that shares one MemoryManager between threads.
+///
+/// Every operation locks an internal mutex for its duration and returns owned results,
+/// so no borrow of the arena outlives the lock. The single-threaded MemoryManager is left
+/// untouched and remains lock-free.
+///
+/// Attributes:
+///     inner (Mutex<MemoryManager>): The guarded memory manager.
+pub struct SyncMemoryManager {
+    inner: Mutex<MemoryManager>,
+}
+
+impl Default for SyncMemoryManager {
+    fn default() -> Self {
+        Self::new(MemoryManager::new())
+    }
+}
+
+impl SyncMemoryManager {
+    /// """Wraps an existing MemoryManager for shared use across threads.
+    ///
+    /// Args:
+    ///     manager (MemoryManager): The memory manager to guard.
+    ///
+    /// Returns:
+    ///     SyncMemoryManager: A wrapper that can be shared, e.g. through an `Arc`.
+    /// """
+    pub fn new(manager: MemoryManager) -> Self {
+        Self {
+            inner: Mutex::new(manager),
+        }
+    }
+
+    /// """Inserts data into memory while holding the lock.
+    ///
+    /// Args:
+    ///     size (usize): The number of bytes to allocate.
+    ///     data (&[u8]): A byte slice containing the data to be stored.
+    ///
+    /// Returns:
+    ///     Result<usize, AllocError>: A unique allocation ID, or `OutOfMemory` if insufficient space is available.
+    /// """
+    pub fn insert(&self, size: usize, data: &[u8]) -> Result<usize, AllocError> {
+        self.lock().insert(size, data)
+    }
+
+    /// """Frees an allocated memory block by its unique ID while holding the lock.
+    ///
+    /// Args:
+    ///     id (usize): The unique allocation ID of the block to be freed.
+    ///
+    /// Returns:
+    ///     Result<(), AllocError>: Ok(()) if the block was freed, or `UnknownId` if the ID is not allocated.
+    /// """
+    pub fn delete(&self, id: usize) -> Result<(), AllocError> {
+        self.lock().delete(id)
+    }
+
+    /// """Copies the data of an allocated block out of the arena.
+    ///
+    /// Unlike `MemoryManager::find`, the data cannot be borrowed past the lock, so every
+    /// call copies the whole block into a new vector. Prefer it for small blocks or
+    /// infrequent reads.
+    ///
+    /// Args:
+    ///     id (usize): The unique allocation ID to look up.
+    ///
+    /// Returns:
+    ///     Option<Vec<u8>>: A copy of the block's data if found, or None otherwise.
+    /// """
+    pub fn find(&self, id: usize) -> Option<Vec<u8>> {
+        self.lock().find(id).map(<[u8]>::to_vec)
+    }
+
+    /// """Summarizes current memory usage while holding the lock.
+    ///
+    /// Returns:
+    ///     MemoryStats: A snapshot of the manager's statistics.
+    /// """
+    pub fn stats(&self) -> MemoryStats {
+        self.lock().stats()
+    }
+
+    /// """Consumes the wrapper and returns the guarded MemoryManager.
+    ///
+    /// Returns:
+    ///     MemoryManager: The inner memory manager.
+    /// """
+    pub fn into_inner(self) -> MemoryManager {
+        self.inner
+            .into_inner()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// """Acquires the lock, recovering the manager if another thread panicked while holding it.
+    ///
+    /// Returns:
+    ///     MutexGuard<MemoryManager>: A guard granting exclusive access to the manager.
+    /// """
+    fn lock(&self) -> MutexGuard<'_, MemoryManager> {
+        self.inner.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}